@@ -0,0 +1,200 @@
+use reqwest::Client;
+use serde_json::json;
+
+use crate::{
+    api_service,
+    audit::{self, AuditAction, AuditRecord, AuditTarget},
+    config::Config,
+    errors::{APIError, AuditError},
+    hooks::{self, HookEvent},
+    message,
+    protection,
+};
+
+
+pub enum ActionError {
+    Api(APIError),
+    Audit(AuditError),
+    /// Descriptions of the protected connections that blocked a disconnect.
+    Protected(Vec<String>),
+    /// IDs no longer in the listing, which can't be checked against `[[protected]]`.
+    NotFound(Vec<String>),
+    /// The action is blocked by read-only mode.
+    ReadOnly(AuditAction),
+    /// No connection was given, so there is nothing to send.
+    NoTargets,
+}
+
+impl std::fmt::Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ActionError::Api(e) => write!(f, "{}", e),
+            ActionError::Audit(e) => write!(f, "{}", e),
+            ActionError::Protected(targets) => write!(f, "Conexões protegidas, nada foi desconectado: {}", targets.join(", ")),
+            ActionError::NotFound(ids) => write!(f, "Conexões fora da listagem, nada foi desconectado: {}", ids.join(", ")),
+            ActionError::ReadOnly(action) => write!(f, "Modo somente leitura: {} bloqueada", action.label()),
+            ActionError::NoTargets => write!(f, "Nenhuma conexão selecionada"),
+        }
+    }
+}
+
+
+/// Disconnects the given connections and records the action in the audit log.
+///
+/// The audit record is written even when the request fails, with the error as the response.
+///
+/// If any target matches a `[[protected]]` rule and `allow_protected` is false, nothing is sent
+/// and `ActionError::Protected` lists the protected targets. In read-only mode nothing is sent
+/// either, regardless of `allow_protected`.
+///
+/// Targets that weren't found in the listing (`AuditTarget::unknown`) can't be checked against
+/// the rules, so they refuse the whole disconnect with `ActionError::NotFound`, even with
+/// `allow_protected`. An empty `targets` is refused with `ActionError::NoTargets`, since the
+/// request would go out without any ID.
+///
+/// # Arguments
+///
+/// * `config` - Configuration used for the API request and the audit file path.
+/// * `targets` - Snapshots of the connections to be disconnected.
+/// * `allow_protected` - Explicit override to disconnect protected connections too.
+/// * `api_client` - The `climonitor serve` client that asked for it, recorded in the audit log.
+/// * `token` - The authorization token for the API request.
+/// * `client` - The HTTP client used to make the API request.
+pub async fn disconnect(config: &Config, targets: &[AuditTarget], allow_protected: bool, api_client: Option<&str>, token: &str, client: &Client) -> Result<String, ActionError> {
+    if config.blocks(AuditAction::Delete) {
+        return Err(ActionError::ReadOnly(AuditAction::Delete));
+    }
+    if targets.is_empty() {
+        return Err(ActionError::NoTargets);
+    }
+    let unknown: Vec<String> = targets.iter().filter(|t| t.is_unknown()).map(|t| t.id.clone()).collect();
+    if !unknown.is_empty() {
+        return Err(ActionError::NotFound(unknown));
+    }
+    if !allow_protected {
+        let protected: Vec<String> = targets
+            .iter()
+            .filter(|t| protection::is_protected(&config.protected, t))
+            .map(|t| t.describe())
+            .collect();
+        if !protected.is_empty() {
+            return Err(ActionError::Protected(protected));
+        }
+    }
+
+    let ids: Vec<String> = targets.iter().map(|t| t.id.clone()).collect();
+    let result = api_service::delete_connections(config, &ids, token, client).await;
+
+    let response = match &result {
+        Ok(body) => body.clone(),
+        Err(e) => e.to_string(),
+    };
+    let record = AuditRecord::new(config, AuditAction::Delete, targets, None, &response, api_client);
+    audit::append(&config.audit_file, &record).map_err(ActionError::Audit)?;
+
+    let response = result.map_err(ActionError::Api)?;
+    hooks::fire(
+        config,
+        HookEvent::Disconnect,
+        format!("{} desconectou {}", record.requester(), hooks::describe(targets)),
+        json!({ "targets": targets, "forced": allow_protected, "api_client": api_client }),
+    );
+    Ok(response)
+}
+
+
+/// Result of [`send_message`], batch by batch.
+pub struct MessageOutcome {
+    /// Number of connections whose batch the server accepted.
+    pub sent: usize,
+    /// IDs of each rejected batch and the reason the server or the request gave.
+    pub failed: Vec<(Vec<String>, String)>,
+}
+
+impl MessageOutcome {
+    /// Number of connections that didn't get the message.
+    pub fn failed_count(&self) -> usize {
+        self.failed.iter().map(|(ids, _)| ids.len()).sum()
+    }
+}
+
+impl std::fmt::Display for MessageOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let failures: Vec<String> = self.failed.iter().map(|(ids, reason)| format!("{}: {}", ids.join(", "), reason)).collect();
+        write!(
+            f,
+            "Mensagem enviada para {} de {} conexões. Falhou para {}",
+            self.sent,
+            self.sent + self.failed_count(),
+            failures.join("; ")
+        )
+    }
+}
+
+
+/// Sends a message to the given connections and records the action in the audit log.
+///
+/// Placeholders such as `{user}` are filled in for each target. Targets that end up with the
+/// same text share one request and one audit record, so a message without placeholders is still
+/// a single request. Every batch is sent even if an earlier one fails, and the result says which
+/// connections got the message and which didn't. Only a failure to write the audit log stops the
+/// remaining batches, since nothing may be sent without a record.
+///
+/// Nothing is sent when read-only mode is set to block messages too.
+///
+/// # Arguments
+///
+/// * `config` - Configuration used for the API request and the audit file path.
+/// * `targets` - Snapshots of the connections that will receive the message.
+/// * `message` - The message to be sent.
+/// * `api_client` - The `climonitor serve` client that asked for it, recorded in the audit log.
+/// * `token` - The authorization token for the API request.
+/// * `client` - The HTTP client used to make the API request.
+pub async fn send_message(config: &Config, targets: &[AuditTarget], message: &str, api_client: Option<&str>, token: &str, client: &Client) -> Result<MessageOutcome, ActionError> {
+    if config.blocks(AuditAction::Message) {
+        return Err(ActionError::ReadOnly(AuditAction::Message));
+    }
+    if targets.is_empty() {
+        return Err(ActionError::NoTargets);
+    }
+    let mut batches: Vec<(String, Vec<AuditTarget>)> = Vec::new();
+    for target in targets {
+        let text = message::fill(message, target);
+        match batches.iter_mut().find(|(t, _)| *t == text) {
+            Some((_, batch)) => batch.push(target.clone()),
+            None => batches.push((text, vec![target.clone()])),
+        }
+    }
+
+    let mut outcome = MessageOutcome { sent: 0, failed: Vec::new() };
+    for (text, batch) in &batches {
+        let ids: Vec<String> = batch.iter().map(|t| t.id.clone()).collect();
+        let result = api_service::send_messages(config, &ids, text, token, client).await;
+
+        let response = match &result {
+            Ok(resp) => format!("level {} {}", resp.level, resp.message.clone().unwrap_or_default()).trim().to_string(),
+            Err(e) => e.to_string(),
+        };
+        let record = AuditRecord::new(config, AuditAction::Message, batch, Some(text), &response, api_client);
+        audit::append(&config.audit_file, &record).map_err(ActionError::Audit)?;
+
+        // Uma mensagem na resposta do servidor indica que o lote não foi entregue.
+        let failure = match result {
+            Ok(resp) => resp.message.filter(|m| !m.is_empty()),
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(reason) = failure {
+            outcome.failed.push((ids, reason));
+            continue;
+        }
+        hooks::fire(
+            config,
+            HookEvent::Message,
+            format!("{} enviou \"{}\" para {}", record.requester(), text, hooks::describe(batch)),
+            json!({ "targets": batch, "message": text, "api_client": api_client }),
+        );
+        outcome.sent += batch.len();
+    }
+
+    Ok(outcome)
+}
@@ -0,0 +1,283 @@
+use std::{io::Write, time::Instant};
+
+use chrono::{DateTime, Local};
+use ratatui::{style::Modifier, text::{Line, Span}};
+use serde::{Deserialize, Serialize};
+
+use crate::{api_service::Entry, theme::Theme, trends};
+
+/// Quantidade máxima de alertas mantidos no histórico da sessão.
+const CAPACITY: usize = 200;
+/// Meio período do pisca do aviso.
+const FLASH_MILLIS: u128 = 500;
+
+
+/// O que uma regra de `[[alerts]]` mede.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// Total de conexões da listagem acima de `above`.
+    Connections,
+    /// Mais de `count_above` conexões usando mais de `above` MB.
+    MemoryMb,
+    /// Mais de `count_above` conexões inativas há mais de `above` minutos.
+    InactiveMinutes,
+    /// A listagem não responde.
+    Unreachable,
+}
+
+
+/// Regra de `[[alerts]]` no config.toml, avaliada a cada atualização.
+///
+/// ```toml
+/// [[alerts]]
+/// metric = "inactive_minutes"
+/// above = 120
+/// count_above = 20
+/// bell = true
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AlertRule {
+    /// Nome mostrado no aviso; sem ele, a regra é descrita pela métrica.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    pub metric: AlertMetric,
+    #[serde(default)]
+    pub above: u64,
+    #[serde(default)]
+    pub count_above: usize,
+    /// Toca o sino do terminal quando o alerta começa.
+    #[serde(default)]
+    pub bell: bool,
+}
+
+impl AlertRule {
+    pub fn label(&self) -> String {
+        if !self.name.is_empty() {
+            return self.name.clone();
+        }
+        let count = |one: &str, many: &str| match self.count_above {
+            0 => format!("conexão {} {}", one, self.above),
+            n => format!("mais de {} conexões {} {}", n, many, self.above),
+        };
+        match self.metric {
+            AlertMetric::Connections => format!("mais de {} conexões", self.above),
+            AlertMetric::MemoryMb => count("com mais de", "com mais de") + " MB",
+            AlertMetric::InactiveMinutes => count("inativa há mais de", "inativas há mais de") + " min",
+            AlertMetric::Unreachable => "servidor sem resposta".to_string(),
+        }
+    }
+
+    /// Detalhe do alerta se a regra dispara com esta listagem, ou `None` se não dispara.
+    fn check(&self, entries: &[Entry]) -> Option<String> {
+        let matching: Vec<&Entry> = match self.metric {
+            AlertMetric::Connections => {
+                return (entries.len() as u64 > self.above).then(|| format!("{} conexões", entries.len()));
+            }
+            AlertMetric::Unreachable => return None,
+            AlertMetric::MemoryMb => entries
+                .iter()
+                .filter(|e| e.memory.max(0) as u64 > self.above.saturating_mul(1024 * 1024))
+                .collect(),
+            AlertMetric::InactiveMinutes => entries
+                .iter()
+                .filter(|e| e.inactive_secs().is_some_and(|secs| secs > self.above.saturating_mul(60)))
+                .collect(),
+        };
+        if matching.len() <= self.count_above {
+            return None;
+        }
+
+        let mut worst = matching.clone();
+        match self.metric {
+            AlertMetric::MemoryMb => worst.sort_by_key(|e| std::cmp::Reverse(e.memory)),
+            _ => worst.sort_by_key(|e| std::cmp::Reverse(e.inactive_secs())),
+        }
+        let names: Vec<String> = worst
+            .iter()
+            .take(3)
+            .map(|e| {
+                let value = match self.metric {
+                    AlertMetric::MemoryMb => trends::format_bytes(e.memory.max(0) as u64),
+                    _ => e.inactive_time.trim().to_string(),
+                };
+                format!("{} ({}) {}", e.user_name.trim(), e.function.trim(), value)
+            })
+            .collect();
+        let more = if matching.len() > names.len() { ", ..." } else { "" };
+        Some(format!("{} conexões: {}{}", matching.len(), names.join(", "), more))
+    }
+}
+
+
+/// Um alerta disparado, do início até a regra deixar de valer.
+pub struct AlertRecord {
+    rule: usize,
+    pub label: String,
+    /// Valor atual, atualizado enquanto o alerta está ativo.
+    pub detail: String,
+    pub started: DateTime<Local>,
+    pub ended: Option<DateTime<Local>>,
+    /// Já foi visto no histórico; o aviso para de piscar.
+    seen: bool,
+}
+
+
+/// Alertas ativos e o histórico da sessão.
+#[derive(Default)]
+pub struct Alerts {
+    /// Do mais recente para o mais antigo.
+    pub history: Vec<AlertRecord>,
+    pub scroll: usize,
+    /// Início do pisca, para alternar o estilo do aviso.
+    flash_since: Option<Instant>,
+}
+
+impl Alerts {
+    /// Avalia as regras com a listagem nova, ou com o erro da busca quando ela falha.
+    ///
+    /// Se a listagem falhou, só as regras `unreachable` mudam; as demais continuam como estavam.
+    /// Toca o sino quando começa um alerta de regra com `bell`.
+    pub fn evaluate(&mut self, rules: &[AlertRule], listing: Result<&[Entry], &str>) {
+        let mut ring = false;
+        for (i, rule) in rules.iter().enumerate() {
+            let detail = match (rule.metric, listing) {
+                (AlertMetric::Unreachable, Ok(_)) => None,
+                (AlertMetric::Unreachable, Err(error)) => Some(error.to_string()),
+                (_, Ok(entries)) => rule.check(entries),
+                (_, Err(_)) => continue,
+            };
+
+            let open = self.history.iter_mut().find(|r| r.rule == i && r.ended.is_none());
+            match (open, detail) {
+                (Some(record), Some(detail)) => record.detail = detail,
+                (Some(record), None) => record.ended = Some(Local::now()),
+                (None, Some(detail)) => {
+                    ring |= rule.bell;
+                    self.history.insert(0, AlertRecord {
+                        rule: i,
+                        label: rule.label(),
+                        detail,
+                        started: Local::now(),
+                        ended: None,
+                        seen: false,
+                    });
+                }
+                (None, None) => {}
+            }
+        }
+        self.history.truncate(CAPACITY);
+
+        if self.active().any(|r| !r.seen) {
+            self.flash_since.get_or_insert_with(Instant::now);
+        } else {
+            self.flash_since = None;
+        }
+        if ring {
+            let mut out = std::io::stdout();
+            let _ = out.write_all(b"\x07");
+            let _ = out.flush();
+        }
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &AlertRecord> {
+        self.history.iter().filter(|r| r.ended.is_none())
+    }
+
+    /// Marca os alertas ativos como vistos, ao abrir o histórico.
+    pub fn acknowledge(&mut self) {
+        for record in self.history.iter_mut() {
+            record.seen = true;
+        }
+        self.flash_since = None;
+    }
+
+    /// Linha do aviso no topo da tela, ou `None` sem alertas ativos. Pisca até os alertas
+    /// serem vistos no histórico.
+    pub fn banner(&self, theme: &Theme) -> Option<Line<'static>> {
+        let active: Vec<String> = self
+            .active()
+            .map(|r| format!("{} - {}", r.label, r.detail))
+            .collect();
+        if active.is_empty() {
+            return None;
+        }
+        let style = match self.flash_since {
+            Some(since) if (since.elapsed().as_millis() / FLASH_MILLIS) % 2 == 1 => {
+                theme.danger().add_modifier(Modifier::REVERSED)
+            }
+            _ => theme.danger(),
+        };
+        Some(Line::from(Span::styled(format!(" ALERTA: {} ", active.join(" | ")), style.add_modifier(Modifier::BOLD))))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(metric: AlertMetric, above: u64, count_above: usize) -> AlertRule {
+        AlertRule { name: String::new(), metric, above, count_above, bell: false }
+    }
+
+    fn entry(user_name: &str, memory_mb: i32, inactive_time: &str) -> Entry {
+        Entry {
+            user_name: user_name.to_string(),
+            function: "SIGAADV".to_string(),
+            memory: memory_mb * 1024 * 1024,
+            inactive_time: inactive_time.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn connections_fires_above_the_limit() {
+        let entries = vec![Entry::default(), Entry::default(), Entry::default()];
+        assert_eq!(rule(AlertMetric::Connections, 2, 0).check(&entries).as_deref(), Some("3 conexões"));
+        assert_eq!(rule(AlertMetric::Connections, 3, 0).check(&entries), None);
+    }
+
+    #[test]
+    fn memory_counts_connections_above_the_limit() {
+        let entries = vec![entry("ANA", 300, "00:00:00"), entry("JOAO", 500, "00:00:00"), entry("MARIA", 100, "00:00:00")];
+        let detail = rule(AlertMetric::MemoryMb, 200, 1).check(&entries).unwrap();
+        assert!(detail.starts_with("2 conexões: JOAO"), "{}", detail);
+        assert_eq!(rule(AlertMetric::MemoryMb, 200, 2).check(&entries), None);
+        assert_eq!(rule(AlertMetric::MemoryMb, 500, 0).check(&entries), None);
+    }
+
+    #[test]
+    fn inactive_minutes_compares_in_seconds() {
+        let entries = vec![entry("ANA", 0, "02:00:01"), entry("JOAO", 0, "01:59:59")];
+        let detail = rule(AlertMetric::InactiveMinutes, 120, 0).check(&entries).unwrap();
+        assert!(detail.starts_with("1 conexões: ANA"), "{}", detail);
+    }
+
+    #[test]
+    fn huge_limits_do_not_overflow() {
+        let entries = vec![entry("ANA", i32::MAX / (1024 * 1024), "99:59:59")];
+        assert_eq!(rule(AlertMetric::MemoryMb, u64::MAX, 0).check(&entries), None);
+        assert_eq!(rule(AlertMetric::InactiveMinutes, u64::MAX, 0).check(&entries), None);
+    }
+
+    #[test]
+    fn evaluate_opens_and_closes_alerts() {
+        let rules = vec![rule(AlertMetric::Connections, 1, 0), rule(AlertMetric::Unreachable, 0, 0)];
+        let mut alerts = Alerts::default();
+        let two = vec![Entry::default(), Entry::default()];
+
+        alerts.evaluate(&rules, Ok(&two));
+        assert_eq!(alerts.active().count(), 1);
+        assert!(alerts.banner(&Theme::default()).is_some());
+
+        // Sem listagem, só a regra `unreachable` muda; a de conexões continua ativa.
+        alerts.evaluate(&rules, Err("timeout"));
+        assert_eq!(alerts.active().count(), 2);
+
+        alerts.evaluate(&rules, Ok(&two[..1]));
+        assert_eq!(alerts.active().count(), 0);
+        assert_eq!(alerts.history.len(), 2);
+        assert!(alerts.banner(&Theme::default()).is_none());
+    }
+}
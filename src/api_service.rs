@@ -1,12 +1,18 @@
+use std::{collections::HashSet, time::Duration};
+
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
 use crate::config::Config;
 use crate::errors::APIError;
+use crate::protection;
+
+/// Tempo que um token é reaproveitado antes de autenticar de novo.
+pub const TOKEN_TTL: Duration = Duration::from_secs(30 * 60);
 
 
 
-#[derive(Default,Deserialize)]
+#[derive(Default,Clone,Deserialize,Serialize)]
 pub struct Entry {
     pub id: String,
     #[serde(rename = "userName")]
@@ -37,8 +43,84 @@ pub struct Entry {
     pub inactive_time: String,
 }
 
+impl Entry {
+    /// Tempo de inatividade em segundos.
+    ///
+    /// Aceita `hh:mm:ss`, `mm:ss` e um prefixo opcional de dias (`2 03:04:05`).
+    pub fn inactive_secs(&self) -> Option<u64> {
+        parse_duration_secs(&self.inactive_time)
+    }
+
+    /// Tempo de conexão em segundos, no mesmo formato de `inactive_secs`.
+    pub fn time_up_secs(&self) -> Option<u64> {
+        parse_duration_secs(&self.time_up)
+    }
+}
+
+/// Conexão como devolvida por `climonitor serve` e pela exportação em JSON, com nomes estáveis, independentes dos do WebMonitor.
+#[derive(Serialize)]
+pub struct Connection {
+    id: String,
+    user: String,
+    machine: String,
+    program: String,
+    environment: String,
+    server: String,
+    thread_type: String,
+    thread_id: i32,
+    connected_at: String,
+    time_up: String,
+    inactive_time: String,
+    inactive_secs: Option<u64>,
+    memory_bytes: u64,
+    instructions: i64,
+    instructions_per_second: i64,
+    comments: String,
+    /// Casa com uma regra de `[[protected]]`; só é desconectada com `force`.
+    protected: bool,
+}
+
+impl Connection {
+    pub fn new(entry: &Entry, config: &Config) -> Self {
+        Self {
+            id: entry.id.clone(),
+            user: entry.user_name.trim().to_string(),
+            machine: entry.machine_name.trim().to_string(),
+            program: entry.function.trim().to_string(),
+            environment: entry.environment.trim().to_string(),
+            server: entry.server.trim().to_string(),
+            thread_type: entry.thread_type.trim().to_string(),
+            thread_id: entry.thread_id,
+            connected_at: entry.date_time.trim().to_string(),
+            time_up: entry.time_up.trim().to_string(),
+            inactive_time: entry.inactive_time.trim().to_string(),
+            inactive_secs: entry.inactive_secs(),
+            memory_bytes: entry.memory.max(0) as u64,
+            instructions: entry.instructions,
+            instructions_per_second: entry.instructions_ps as i64,
+            comments: entry.comments.trim().to_string(),
+            protected: protection::is_protected_entry(&config.protected, entry),
+        }
+    }
+}
+
+
+pub fn parse_duration_secs(value: &str) -> Option<u64> {
+    let mut tokens = value.split_whitespace().collect::<Vec<_>>();
+    let clock = tokens.pop()?;
+    let days: u64 = match tokens.first() {
+        Some(d) => d.parse().ok()?,
+        None => 0,
+    };
+
+    let mut secs: u64 = 0;
+    for part in clock.split(':') {
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(days * 86400 + secs)
+}
+
 
-#[allow(dead_code)]
 #[derive(Deserialize)]
 struct Page{
     items: Vec<Entry>,
@@ -92,6 +174,42 @@ pub struct AuthError{
 ///
 /// Returns a vector of `Entry`s.
 pub async fn get_entries(config : &Config,token: &str, client: &Client, page: i32, page_size: i32) -> Result<Vec<Entry>,APIError>{
+    Ok(get_page(config, token, client, page, page_size).await?.items)
+}
+
+
+/// Fetches every page of entries until the server reports there is no next page.
+///
+/// Entries are deduplicated by ID, so overlapping pages don't produce repeated rows.
+///
+/// # Errors
+///
+/// Returns the first `APIError` found while fetching the pages.
+pub async fn get_all_entries(config : &Config,token: &str, client: &Client) -> Result<Vec<Entry>,APIError>{
+    const PAGE_SIZE: i32 = 100;
+    const MAX_PAGES: i32 = 1000;
+
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for page in 0..MAX_PAGES {
+        let pages = get_page(config, token, client, page, PAGE_SIZE).await?;
+        let before = entries.len();
+        for entry in pages.items {
+            if seen.insert(entry.id.clone()) {
+                entries.push(entry);
+            }
+        }
+        if !pages.has_next || entries.len() == before {
+            break;
+        }
+    }
+
+    Ok(entries)
+}
+
+
+async fn get_page(config : &Config,token: &str, client: &Client, page: i32, page_size: i32) -> Result<Page,APIError>{
     let ip = &config.ip;
     let porta = &config.porta;
     let resp_tr = client
@@ -106,13 +224,10 @@ pub async fn get_entries(config : &Config,token: &str, client: &Client, page: i3
         Err(e) => return Err(APIError::RequestError(e.to_string())),
     };
 
-    let pages : Page = match resp.json().await {
-        Ok(pages) => pages,
-        Err(e) => return Err(APIError::ParsingError(e.to_string())),
-    };
-
-    Ok(pages.items)
-                
+    match resp.json().await {
+        Ok(pages) => Ok(pages),
+        Err(e) => Err(APIError::ParsingError(e.to_string())),
+    }
 }
 
 /// Makes a request to the api to get a token.
@@ -172,10 +287,12 @@ pub async fn get_token(config : &Config, client: &Client) -> Result<String,APIEr
 /// * `token` - The authorization token for the API request.
 /// * `client` - The HTTP client used to make the API request.
 ///
+/// # Returns
+///
+/// Returns the status and body sent back by the server.
+pub async fn delete_connections(config : &Config,id: &[String], token: &str, client: &Client) -> Result<String,APIError>{
 
-pub async fn delete_connections(config : &Config,id: &Vec<String>, token: &str, client: &Client) -> Option<APIError>{
-
-    match client
+    let resp = match client
         .delete(format!("http://{}:{}/webmonitor/webmnt/{}",config.ip,config.porta,id.join(",")))
         .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:138.0) Gecko/20100101 Firefox/138.0")
         .header("Accept", "application/json, text/plain")
@@ -183,9 +300,16 @@ pub async fn delete_connections(config : &Config,id: &Vec<String>, token: &str,
         .send()
         .await
     {
-        Ok(_) => return None,
-        Err(e) => return Some(APIError::RequestError(e.to_string())),
-    };   
+        Ok(resp) => resp,
+        Err(e) => return Err(APIError::RequestError(e.to_string())),
+    };
+
+    let status = resp.status();
+    let body = match resp.text().await {
+        Ok(body) => body,
+        Err(e) => return Err(APIError::AsyncError(e.to_string())),
+    };
+    Ok(format!("{} {}", status.as_u16(), body.trim()))
 }
 
 
@@ -207,11 +331,12 @@ pub async fn delete_connections(config : &Config,id: &Vec<String>, token: &str,
 ///
 /// Returns a `MessageResponse` containing the status of the request and the
 /// message that was sent.
-pub async fn send_messages(config : &Config,ids: &Vec<String>, message: &str,token: &str, client: &Client) -> Result<MessageResponse,APIError>{
+pub async fn send_messages(config : &Config,ids: &[String], message: &str,token: &str, client: &Client) -> Result<MessageResponse,APIError>{
     let id_param = serde_json::to_string(&ids).map_err(|e| APIError::ParsingError(e.to_string()))?;
-    let url = format!("http://{}:{}/webmonitor/webmnt/msg?msg={}&id={}",config.ip,config.porta ,message, id_param);
+    let url = format!("http://{}:{}/webmonitor/webmnt/msg",config.ip,config.porta);
     let resp = match client
         .get(url)
+        .query(&[("msg", message), ("id", id_param.as_str())])
         .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:138.0) Gecko/20100101 Firefox/138.0")
         .header("Accept", "application/json, text/plain")
         .header("Authorization", "token: ".to_string() + token)
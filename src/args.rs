@@ -0,0 +1,188 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::{audit::{AuditAction, AuditQuery}, errors::ArgsError, history::HistoryQuery, hooks::HookEvent};
+
+
+pub enum Command {
+    /// Opens the TUI; `read_only` comes from `--read-only`.
+    Monitor { read_only: bool },
+    Audit(AuditQuery),
+    History(HistoryQuery),
+    /// Serves Prometheus metrics; `listen` comes from `--listen`.
+    Exporter { listen: Option<String> },
+    /// Serves the JSON API; `listen` comes from `--listen` and `read_only` from `--read-only`.
+    Serve { listen: Option<String>, read_only: bool },
+    /// Test-fires the `[[hooks]]`, for one event or all of them.
+    Hooks { event: Option<HookEvent> },
+}
+
+
+/// Parses the command line arguments (without the program name).
+///
+/// With no arguments (or only `--read-only`) CLIMonitor opens the TUI. The `audit` subcommand queries the local
+/// audit log instead:
+///
+/// ```text
+/// climonitor audit [--user USUARIO] [--action delete|message] [--since AAAA-MM-DD[ HH:MM]] [--limit N] [--json]
+/// ```
+///
+/// and `history` queries the connection snapshots saved when `[history]` is enabled:
+///
+/// ```text
+/// climonitor history --at "AAAA-MM-DD HH:MM"
+/// climonitor history --user USUARIO [--since AAAA-MM-DD[ HH:MM]]
+/// ```
+///
+/// and `exporter` serves the listing as Prometheus metrics on `/metrics`:
+///
+/// ```text
+/// climonitor exporter [--listen 0.0.0.0:9464]
+/// ```
+///
+/// and `serve` exposes list, kill and msg as a JSON API:
+///
+/// ```text
+/// climonitor serve [--listen 127.0.0.1:9465] [--read-only]
+/// ```
+///
+/// and `hooks test` fires the configured hooks with sample data:
+///
+/// ```text
+/// climonitor hooks test [connections_above|protected_disconnected|disconnect|message|server_unreachable|server_recovered]
+/// ```
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, ArgsError> {
+    match args.next().as_deref() {
+        None => Ok(Command::Monitor { read_only: false }),
+        Some("--read-only") => match args.next() {
+            None => Ok(Command::Monitor { read_only: true }),
+            Some(other) => Err(ArgsError::UnknownArgument(other)),
+        },
+        Some("audit") => parse_audit(args).map(Command::Audit),
+        Some("history") => parse_history(args).map(Command::History),
+        Some("exporter") => parse_exporter(args),
+        Some("serve") => parse_serve(args),
+        Some("hooks") => parse_hooks(args),
+        Some(other) => Err(ArgsError::UnknownArgument(other.to_string())),
+    }
+}
+
+
+fn parse_audit(mut args: impl Iterator<Item = String>) -> Result<AuditQuery, ArgsError> {
+    let mut query = AuditQuery::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--user" => query.user = Some(value(&arg, args.next())?),
+            "--action" => {
+                let v = value(&arg, args.next())?;
+                query.action = match v.as_str() {
+                    "delete" => Some(AuditAction::Delete),
+                    "message" => Some(AuditAction::Message),
+                    _ => return Err(ArgsError::InvalidValue(format!("{} {}", arg, v))),
+                };
+            }
+            "--since" => {
+                let v = value(&arg, args.next())?;
+                query.since = Some(datetime(&arg, &v)?);
+            }
+            "--limit" => {
+                let v = value(&arg, args.next())?;
+                query.limit = match v.parse() {
+                    Ok(n) => Some(n),
+                    Err(_) => return Err(ArgsError::InvalidValue(format!("{} {}", arg, v))),
+                };
+            }
+            "--json" => query.json = true,
+            _ => return Err(ArgsError::UnknownArgument(arg)),
+        }
+    }
+
+    Ok(query)
+}
+
+
+fn parse_history(mut args: impl Iterator<Item = String>) -> Result<HistoryQuery, ArgsError> {
+    let mut query = HistoryQuery::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--at" => {
+                let v = value(&arg, args.next())?;
+                query.at = Some(datetime(&arg, &v)?);
+            }
+            "--user" => query.user = Some(value(&arg, args.next())?),
+            "--since" => {
+                let v = value(&arg, args.next())?;
+                query.since = Some(datetime(&arg, &v)?);
+            }
+            _ => return Err(ArgsError::UnknownArgument(arg)),
+        }
+    }
+
+    Ok(query)
+}
+
+
+fn parse_exporter(mut args: impl Iterator<Item = String>) -> Result<Command, ArgsError> {
+    let mut listen = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => listen = Some(value(&arg, args.next())?),
+            _ => return Err(ArgsError::UnknownArgument(arg)),
+        }
+    }
+
+    Ok(Command::Exporter { listen })
+}
+
+
+fn parse_serve(mut args: impl Iterator<Item = String>) -> Result<Command, ArgsError> {
+    let mut listen = None;
+    let mut read_only = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => listen = Some(value(&arg, args.next())?),
+            "--read-only" => read_only = true,
+            _ => return Err(ArgsError::UnknownArgument(arg)),
+        }
+    }
+
+    Ok(Command::Serve { listen, read_only })
+}
+
+
+fn parse_hooks(mut args: impl Iterator<Item = String>) -> Result<Command, ArgsError> {
+    match args.next().as_deref() {
+        Some("test") => {}
+        Some(other) => return Err(ArgsError::UnknownArgument(other.to_string())),
+        None => return Err(ArgsError::MissingValue("hooks test".to_string())),
+    }
+    let event = match args.next() {
+        Some(name) => Some(HookEvent::from_name(&name).ok_or(ArgsError::InvalidValue(format!("evento {}", name)))?),
+        None => None,
+    };
+    if let Some(other) = args.next() {
+        return Err(ArgsError::UnknownArgument(other));
+    }
+
+    Ok(Command::Hooks { event })
+}
+
+
+/// Parses a date argument, accepting `aaaa-mm-dd` or `aaaa-mm-dd hh:mm`.
+fn datetime(arg: &str, value: &str) -> Result<NaiveDateTime, ArgsError> {
+    if let Ok(t) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M") {
+        return Ok(t);
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .ok_or_else(|| ArgsError::InvalidValue(format!("{} {}", arg, value)))
+}
+
+
+fn value(arg: &str, next: Option<String>) -> Result<String, ArgsError> {
+    next.ok_or_else(|| ArgsError::MissingValue(arg.to_string()))
+}
@@ -0,0 +1,259 @@
+use std::{fs::{self, OpenOptions}, io::Write};
+
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{api_service::{self, Entry}, config::Config, errors::AuditError};
+
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Delete,
+    Message,
+}
+
+impl AuditAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditAction::Delete => "desconexão",
+            AuditAction::Message => "mensagem",
+        }
+    }
+}
+
+/// Snapshot of a connection at the moment an action was taken against it.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditTarget {
+    pub id: String,
+    pub user_name: String,
+    pub machine_name: String,
+    pub function: String,
+    pub environment: String,
+    #[serde(default)]
+    pub thread_type: String,
+    #[serde(default)]
+    pub time_up: String,
+    #[serde(default)]
+    pub inactive_time: String,
+}
+
+impl AuditTarget {
+    pub fn from_entry(entry: &Entry) -> Self {
+        Self {
+            id: entry.id.clone(),
+            user_name: entry.user_name.trim().to_string(),
+            machine_name: entry.machine_name.trim().to_string(),
+            function: entry.function.trim().to_string(),
+            environment: entry.environment.trim().to_string(),
+            thread_type: entry.thread_type.trim().to_string(),
+            time_up: entry.time_up.trim().to_string(),
+            inactive_time: entry.inactive_time.trim().to_string(),
+        }
+    }
+
+    /// Used for IDs that are selected but no longer present in the listing.
+    pub fn unknown(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            user_name: String::new(),
+            machine_name: String::new(),
+            function: String::new(),
+            environment: String::new(),
+            thread_type: String::new(),
+            time_up: String::new(),
+            inactive_time: String::new(),
+        }
+    }
+
+    /// Built by `unknown`: the connection wasn't found, so its fields can't be checked.
+    pub fn is_unknown(&self) -> bool {
+        self.user_name.is_empty() && self.machine_name.is_empty() && self.function.is_empty()
+    }
+
+    pub fn inactive_secs(&self) -> Option<u64> {
+        api_service::parse_duration_secs(&self.inactive_time)
+    }
+
+    pub fn describe(&self) -> String {
+        if self.user_name.is_empty() {
+            return format!("#{}", self.id);
+        }
+        format!("{}@{} ({})", self.user_name, self.machine_name, self.function)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    pub os_user: String,
+    pub server: String,
+    pub environment: String,
+    pub action: AuditAction,
+    pub targets: Vec<AuditTarget>,
+    pub message: Option<String>,
+    pub response: String,
+    /// Cliente da API de `climonitor serve` que pediu a ação; ausente nas ações feitas no terminal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_client: Option<String>,
+}
+
+impl AuditRecord {
+    pub fn new(config: &Config, action: AuditAction, targets: &[AuditTarget], message: Option<&str>, response: &str, api_client: Option<&str>) -> Self {
+        Self {
+            timestamp: Local::now().to_rfc3339(),
+            os_user: os_user(),
+            server: format!("{}:{}", config.ip, config.porta),
+            environment: config.enviorment.clone(),
+            action,
+            targets: targets.to_vec(),
+            message: message.map(|m| m.to_string()),
+            response: response.to_string(),
+            api_client: api_client.map(|c| c.to_string()),
+        }
+    }
+
+    /// Quem pediu a ação: o usuário do sistema e, se veio pela API, o cliente.
+    pub fn requester(&self) -> String {
+        match &self.api_client {
+            Some(client) => format!("{} (API {})", self.os_user, client),
+            None => self.os_user.clone(),
+        }
+    }
+
+    /// Formats the timestamp as `dd/mm/aaaa hh:mm:ss` in local time.
+    pub fn local_time(&self) -> String {
+        match DateTime::parse_from_rfc3339(&self.timestamp) {
+            Ok(t) => t.with_timezone(&Local).format("%d/%m/%Y %H:%M:%S").to_string(),
+            Err(_) => self.timestamp.clone(),
+        }
+    }
+}
+
+
+/// Filters accepted by `climonitor audit` and the audit viewer.
+#[derive(Default)]
+pub struct AuditQuery {
+    pub user: Option<String>,
+    pub action: Option<AuditAction>,
+    pub since: Option<NaiveDateTime>,
+    pub limit: Option<usize>,
+    pub json: bool,
+}
+
+impl AuditQuery {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        if let Some(action) = self.action && record.action != action {
+            return false;
+        }
+        if let Some(user) = &self.user {
+            let user = user.to_lowercase();
+            let hit = record.os_user.to_lowercase() == user
+                || record.targets.iter().any(|t| t.user_name.to_lowercase() == user);
+            if !hit {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            match DateTime::parse_from_rfc3339(&record.timestamp) {
+                Ok(t) if t.with_timezone(&Local).naive_local() >= since => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+
+/// Returns the name of the operating system user running CLIMonitor.
+pub fn os_user() -> String {
+    std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "desconhecido".to_string())
+}
+
+
+/// Appends a record as a single JSON line to the audit file.
+///
+/// # Errors
+///
+/// Returns an `AuditError` if the file can't be opened or written.
+pub fn append(path: &str, record: &AuditRecord) -> Result<(), AuditError> {
+    let line = serde_json::to_string(record).map_err(|e| AuditError::Parsing(e.to_string()))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| AuditError::OpenFileError(e.to_string()))?;
+    writeln!(file, "{}", line).map_err(|e| AuditError::WriteFileError(e.to_string()))
+}
+
+
+/// Reads the audit file and returns the records matching the query, newest first.
+///
+/// A missing file is treated as an empty log.
+pub fn read_records(path: &str, query: &AuditQuery) -> Result<Vec<AuditRecord>, AuditError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AuditError::OpenFileError(e.to_string())),
+    };
+
+    let mut records = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: AuditRecord = serde_json::from_str(line)
+            .map_err(|e| AuditError::Parsing(format!("linha {}: {}", i + 1, e)))?;
+        if query.matches(&record) {
+            records.push(record);
+        }
+    }
+
+    records.reverse();
+    if let Some(limit) = query.limit {
+        records.truncate(limit);
+    }
+    Ok(records)
+}
+
+
+/// Runs the `climonitor audit` subcommand, printing the matching records to stdout.
+pub fn print_records(config: &Config, query: &AuditQuery) -> Result<(), AuditError> {
+    let records = read_records(&config.audit_file, query)?;
+
+    if query.json {
+        for record in &records {
+            let line = serde_json::to_string(record).map_err(|e| AuditError::Parsing(e.to_string()))?;
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    if records.is_empty() {
+        println!("Nenhum registro de auditoria encontrado.");
+        return Ok(());
+    }
+
+    for record in &records {
+        println!(
+            "{} | {} | {} | {} ({})",
+            record.local_time(),
+            record.requester(),
+            record.action.label(),
+            record.server,
+            record.environment,
+        );
+        for target in &record.targets {
+            println!("    - {}", target.describe());
+        }
+        if let Some(message) = &record.message {
+            println!("    mensagem: {}", message);
+        }
+        if !record.response.is_empty() {
+            println!("    resposta: {}", record.response);
+        }
+    }
+    Ok(())
+}
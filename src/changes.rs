@@ -0,0 +1,126 @@
+use std::{collections::{HashMap, HashSet}, time::{Duration, Instant}};
+
+use crate::{api_service::Entry, config::HighlightConfig};
+
+
+/// Diferenças entre duas atualizações consecutivas da listagem completa.
+///
+/// Conexões novas, desconectadas e com salto de memória ou de instruções ficam marcadas
+/// por `fade_secs` segundos a partir da atualização em que apareceram.
+#[derive(Default)]
+pub struct Changes {
+    previous: Option<HashMap<String, Entry>>,
+    added: HashMap<String, Instant>,
+    jumped: HashMap<String, Instant>,
+    /// Conexões que saíram, e se estavam na página da tabela quando saíram.
+    removed: Vec<(Entry, Instant, bool)>,
+    fade: Duration,
+    pub last_added: usize,
+    pub last_removed: usize,
+}
+
+impl Changes {
+    /// Compara a nova listagem com a anterior, pelo `Entry.id`.
+    ///
+    /// A primeira listagem só serve de base e não marca nada como novo. `shown` são as conexões
+    /// da página exibida, para as que saíram continuarem riscadas só nela.
+    pub fn update(&mut self, entries: &[Entry], config: &HighlightConfig, shown: &HashSet<String>) {
+        let now = Instant::now();
+        self.fade = Duration::from_secs(config.fade_secs);
+        let current: HashMap<String, Entry> = entries.iter().map(|e| (e.id.clone(), e.clone())).collect();
+
+        let Some(previous) = self.previous.take() else {
+            self.previous = Some(current);
+            return;
+        };
+
+        self.last_added = 0;
+        self.last_removed = 0;
+
+        for entry in entries {
+            match previous.get(&entry.id) {
+                None => {
+                    self.added.insert(entry.id.clone(), now);
+                    self.last_added += 1;
+                }
+                Some(before) => {
+                    let memory_jump = entry.memory as i64 - before.memory as i64 >= config.memory_jump_mb as i64 * 1024 * 1024;
+                    let instructions_jump = entry.instructions_ps as i64 - before.instructions_ps as i64 >= config.instructions_jump as i64;
+                    if memory_jump || instructions_jump {
+                        self.jumped.insert(entry.id.clone(), now);
+                    }
+                }
+            }
+        }
+
+        for (id, entry) in previous {
+            if !current.contains_key(&id) {
+                let was_shown = shown.contains(&id);
+                self.removed.push((entry, now, was_shown));
+                self.last_removed += 1;
+            }
+        }
+
+        self.previous = Some(current);
+    }
+
+    /// Descarta as marcações mais antigas que `fade_secs`.
+    pub fn expire(&mut self) {
+        let fade = self.fade;
+        self.added.retain(|_, t| t.elapsed() < fade);
+        self.jumped.retain(|_, t| t.elapsed() < fade);
+        self.removed.retain(|(_, t, _)| t.elapsed() < fade);
+    }
+
+    pub fn is_new(&self, id: &str) -> bool {
+        self.added.contains_key(id)
+    }
+
+    pub fn has_jumped(&self, id: &str) -> bool {
+        self.jumped.contains_key(id)
+    }
+
+    /// Conexões que sumiram da listagem recentemente, com a indicação de se estavam na página.
+    pub fn removed(&self) -> impl Iterator<Item = (&Entry, bool)> {
+        self.removed.iter().map(|(e, _, shown)| (e, *shown))
+    }
+
+    /// A página mudou: as conexões que saíram da anterior não são mais riscadas na tabela.
+    pub fn leave_page(&mut self) {
+        for (_, _, shown) in self.removed.iter_mut() {
+            *shown = false;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str) -> Entry {
+        Entry { id: id.to_string(), ..Default::default() }
+    }
+
+    fn removed(changes: &Changes) -> Vec<(&str, bool)> {
+        let mut removed: Vec<(&str, bool)> = changes.removed().map(|(e, shown)| (e.id.as_str(), shown)).collect();
+        removed.sort();
+        removed
+    }
+
+    #[test]
+    fn removed_rows_remember_if_they_were_on_the_page() {
+        let config = HighlightConfig::default();
+        let mut changes = Changes::default();
+        let page: HashSet<String> = ["1".to_string()].into();
+
+        changes.update(&[entry("1"), entry("2"), entry("3")], &config, &page);
+        changes.update(&[entry("3"), entry("4")], &config, &page);
+        assert_eq!(removed(&changes), [("1", true), ("2", false)]);
+        assert_eq!((changes.last_added, changes.last_removed), (1, 2));
+        assert!(changes.is_new("4"));
+
+        changes.leave_page();
+        assert_eq!(removed(&changes), [("1", false), ("2", false)]);
+    }
+}
@@ -1,54 +1,415 @@
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode}, 
-    layout::{Constraint, Direction, Layout}, 
+    crossterm::event::{self, Event, KeyCode, MouseButton, MouseEvent, MouseEventKind}, 
+    layout::{Constraint, Direction, Flex, Layout, Margin, Rect}, 
     style::{Modifier, Style}, 
-    widgets::{Block, Borders, Cell, Row, Table, Wrap}, 
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState, Wrap}, 
     Frame
 };
 use reqwest::Client;
-use std::{collections::HashSet, error::Error};
-use ratatui::style::Color;
-use crate::{api_service::{self, Entry}, config, modal};
+use std::{collections::HashSet, error::Error, time::{Duration, Instant}};
+use crate::{alerts::Alerts, api_service::{self, Entry}, export::{self, Export, ExportFormat}, audit::{self, AuditAction, AuditQuery, AuditRecord, AuditTarget}, changes::Changes, detail::Detail, grouping::{self, GroupRow, Grouping}, duplicates::Duplicates, columns::{ColumnConfig, ColumnPicker, Field}, config, history, hooks, keymap::{Action, Context, KeyMap}, message::Composer, modal, palette::Palette, protection::{self, ProtectedRule}, text_input::TextInput, theme::Theme, trends::{self, Trends}};
 
 pub struct CliMonitor {
-    pub selected: i32,
+    pub table_state: TableState,
+    /// ID da linha selecionada, para manter a seleção quando a página é atualizada.
+    pub selected_id: Option<String>,
+    /// Quantidade de linhas que cabem na tabela, calculada a cada renderização.
+    pub viewport_rows: usize,
     pub on_modal: bool,
     pub current_modal : Modal,
     pub error: MonitorError,
     pub is_on_error: bool,
     pub is_adding_selected: bool,
     pub item_hash_set: HashSet<String>,
+    /// Seleção do usuário guardada enquanto a confirmação de `K` usa as duplicadas no lugar dela.
+    saved_selection: Option<HashSet<String>>,
+    /// Conexões da página exibida na última renderização, sem filtro nem agrupamento.
+    shown: HashSet<String>,
+    pub audit_records: Vec<AuditRecord>,
+    pub audit_scroll: usize,
+    /// Alertas de `[[alerts]]` ativos e o histórico da sessão.
+    pub alerts: Alerts,
+    pub export: Export,
+    pub all_entries: Vec<Entry>,
+    pub trends: Trends,
+    pub listing_error: Option<String>,
+    /// Estado da listagem para os eventos de `[[hooks]]`.
+    pub hook_watch: hooks::Watch,
+    pub show_dashboard: bool,
+    pub changes: Changes,
+    pub columns: Vec<ColumnConfig>,
+    pub column_picker: ColumnPicker,
+    /// Coluna e direção (crescente = true) da ordenação escolhida clicando no cabeçalho.
+    pub sort: Option<(Field, bool)>,
+    pub table_area: Rect,
+    pub column_areas: Vec<Rect>,
+    pub frame_area: Rect,
+    last_click: Option<(Instant, usize)>,
+    pub context_menu: modal::ContextMenu,
+    pub keymap: KeyMap,
+    pub palette: Palette,
+    pub help_scroll: usize,
+    /// Conexão aberta na tela de detalhes. Enquanto existir, desconectar e enviar mensagem
+    /// agem sobre ela, e não sobre a seleção da tabela.
+    pub detail: Option<Detail>,
+    /// Tabela agrupada pela listagem completa, quando ativa.
+    pub grouping: Option<Grouping>,
+    pub duplicates: Duplicates,
+    /// Mostra só as sessões duplicadas da listagem completa, no lugar da página.
+    pub duplicates_only: bool,
+    /// Texto da barra de filtro (`/`). Com ele preenchido, a tabela mostra as conexões da listagem
+    /// completa que o contêm em alguma coluna.
+    pub filter: TextInput,
+    /// Linha onde começou a seleção por intervalo (Shift+↑ ↓).
+    pub range_anchor: Option<String>,
+    /// IDs marcados pelo intervalo atual, desmarcados quando ele muda de tamanho.
+    pub range_ids: Vec<String>,
+    /// Linha sob o cursor na revisão das selecionadas.
+    pub review_cursor: usize,
+    /// O que já foi digitado da palavra de confirmação de uma desconexão em lote.
+    pub confirm_input: TextInput,
+    /// Regras de `[[protected]]`, para o cadeado na tabela.
+    pub protected: Vec<ProtectedRule>,
+    /// Ações bloqueadas pelo modo somente leitura.
+    pub blocked: Vec<AuditAction>,
+    /// Tema da sessão, passado às funções de desenho.
+    pub theme: Theme,
 }
 
 pub enum MonitorError{
     None,
     SendMsgError(String),
+    DeleteError(String),
+    AuditError(String),
+    HistoryError(String),
+    ConfigError(String),
+    KeyMapError(String),
+    ReadOnly(String),
+    ThemeError(String),
+    HookError(String),
+    ExportError(String),
 }
 
 pub enum Modal{
     Delete,
     Info,
     SendMsg,
+    Audit,
+    Columns,
+    Context,
+    Help,
+    Palette,
+    Detail,
+    Review,
+    Filter,
+    Alerts,
+    Export,
     None
 }
 
 impl CliMonitor {
-    pub fn new() -> Self {
+    pub fn new(columns: &[ColumnConfig]) -> Self {
         Self { 
-            selected: 0 , 
+            table_state: TableState::default().with_selected(Some(0)), 
+            selected_id: None,
+            viewport_rows: 0,
             on_modal: false, 
             current_modal: Modal::None, 
             error: MonitorError::None,
             is_on_error: false,
             is_adding_selected: false,
             item_hash_set: HashSet::new(),
+            saved_selection: None,
+            shown: HashSet::new(),
+            audit_records: Vec::new(),
+            audit_scroll: 0,
+            alerts: Alerts::default(),
+            export: Export::default(),
+            all_entries: Vec::new(),
+            trends: Trends::default(),
+            listing_error: None,
+            hook_watch: hooks::Watch::default(),
+            show_dashboard: true,
+            changes: Changes::default(),
+            columns: columns.to_vec(),
+            column_picker: ColumnPicker::new(columns),
+            sort: None,
+            table_area: Rect::default(),
+            column_areas: Vec::new(),
+            frame_area: Rect::default(),
+            last_click: None,
+            context_menu: modal::ContextMenu::default(),
+            keymap: KeyMap::default(),
+            palette: Palette::default(),
+            help_scroll: 0,
+            detail: None,
+            grouping: None,
+            duplicates: Duplicates::default(),
+            duplicates_only: false,
+            filter: TextInput::default(),
+            range_anchor: None,
+            range_ids: Vec::new(),
+            review_cursor: 0,
+            confirm_input: TextInput::default(),
+            protected: Vec::new(),
+            blocked: Vec::new(),
+            theme: Theme::initial(),
         }
     }
 
+    /// Abre um modal. No modo somente leitura, desconectar e enviar mensagem mostram o aviso no lugar.
     pub fn set_modal(&mut self, modal: Modal) {
+        let action = match modal {
+            Modal::Delete => Some(AuditAction::Delete),
+            Modal::SendMsg => Some(AuditAction::Message),
+            _ => None,
+        };
+        if let Some(action) = action
+            && self.blocked.contains(&action)
+        {
+            // O aviso fecha também a tela de detalhes, para ela não continuar como alvo das ações.
+            self.on_modal = false;
+            self.detail = None;
+            self.error = MonitorError::ReadOnly(format!("A {} está bloqueada neste modo.", action.label()));
+            self.is_on_error = true;
+            return;
+        }
         self.on_modal = true;
         self.current_modal = modal
     }
+
+    /// Devolve a seleção que `K` guardou ao abrir a confirmação, se houver.
+    pub fn restore_selection(&mut self) {
+        if let Some(selection) = self.saved_selection.take() {
+            self.item_hash_set = selection;
+        }
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.table_state.selected().unwrap_or(0)
+    }
+
+    /// Conexão sob o cursor. Na tabela agrupada, `None` quando o cursor está no cabeçalho de um grupo.
+    pub fn selected_entry<'a>(&'a self, entries: &'a [Entry]) -> Option<&'a Entry> {
+        match &self.grouping {
+            Some(grouping) => grouping.current_entry(),
+            None => entries.get(self.selected_index()),
+        }
+    }
+
+    /// Quantidade de linhas da tabela: a página ou as linhas da tabela agrupada.
+    pub fn row_count(&self, entries: &[Entry]) -> usize {
+        match &self.grouping {
+            Some(grouping) => grouping.rows().len(),
+            None => entries.len(),
+        }
+    }
+
+    /// Seleciona a linha `index`, limitada ao tamanho da página.
+    pub fn select(&mut self, index: usize, entries: &[Entry]) {
+        if let Some(grouping) = &mut self.grouping {
+            grouping.select(index);
+            return;
+        }
+        if entries.is_empty() {
+            self.table_state.select(None);
+            self.selected_id = None;
+            return;
+        }
+        let index = index.min(entries.len() - 1);
+        self.table_state.select(Some(index));
+        self.selected_id = Some(entries[index].id.clone());
+    }
+
+    /// Move a seleção `delta` linhas. Com `wrap`, passar do fim volta ao começo e vice-versa.
+    pub fn move_selection(&mut self, delta: isize, wrap: bool, entries: &[Entry]) {
+        if let Some(grouping) = &mut self.grouping {
+            grouping.move_cursor(delta, wrap);
+            return;
+        }
+        let len = entries.len() as isize;
+        if len == 0 {
+            return;
+        }
+        let target = self.selected_index() as isize + delta;
+        let target = if wrap { target.rem_euclid(len) } else { target.clamp(0, len - 1) };
+        self.select(target as usize, entries);
+    }
+
+    fn cursor_index(&self) -> usize {
+        match &self.grouping {
+            Some(grouping) => grouping.state.selected().unwrap_or(0),
+            None => self.selected_index(),
+        }
+    }
+
+    /// IDs das linhas da tabela, na ordem exibida. Na tabela agrupada os cabeçalhos de grupo são `None`.
+    fn row_ids(&self, entries: &[Entry]) -> Vec<Option<String>> {
+        match &self.grouping {
+            Some(grouping) => grouping.rows().into_iter().map(|row| match row {
+                GroupRow::Member(g, m) => Some(grouping.groups[g].members[m].id.clone()),
+                GroupRow::Group(_) => None,
+            }).collect(),
+            None => entries.iter().map(|e| Some(e.id.clone())).collect(),
+        }
+    }
+
+    /// Marca todas as conexões de `ids`, ou desmarca todas se já estavam marcadas.
+    fn toggle_all(&mut self, ids: Vec<String>) {
+        if ids.iter().all(|id| self.item_hash_set.contains(id)) {
+            for id in &ids {
+                self.item_hash_set.remove(id);
+            }
+        } else {
+            self.item_hash_set.extend(ids);
+        }
+    }
+
+    /// Move o cursor `delta` linhas e marca o intervalo entre a linha onde a seleção por intervalo
+    /// começou e a nova posição.
+    ///
+    /// Voltar na direção da âncora desmarca as linhas que saíram do intervalo, mas não as que já
+    /// estavam marcadas antes dele.
+    pub fn extend_selection(&mut self, delta: isize, entries: &[Entry]) {
+        let rows = self.row_ids(entries);
+        if rows.is_empty() {
+            return;
+        }
+        if self.range_anchor.is_none() {
+            self.range_anchor = rows.get(self.cursor_index()).cloned().flatten();
+            self.range_ids.clear();
+        }
+        self.move_selection(delta, false, entries);
+
+        let cursor = self.cursor_index().min(rows.len() - 1);
+        let anchor = self.range_anchor
+            .as_ref()
+            .and_then(|anchor| rows.iter().position(|id| id.as_ref() == Some(anchor)))
+            .unwrap_or(cursor);
+        for id in self.range_ids.drain(..) {
+            self.item_hash_set.remove(&id);
+        }
+        for id in rows[anchor.min(cursor)..=anchor.max(cursor)].iter().flatten() {
+            if self.item_hash_set.insert(id.clone()) {
+                self.range_ids.push(id.clone());
+            }
+        }
+    }
+
+    /// Marca todas as linhas visíveis, ou desmarca se todas já estavam marcadas.
+    pub fn select_visible(&mut self, entries: &[Entry]) {
+        let ids = self.row_ids(entries).into_iter().flatten().collect();
+        self.toggle_all(ids);
+    }
+
+    pub fn invert_selection(&mut self, entries: &[Entry]) {
+        for id in self.row_ids(entries).into_iter().flatten() {
+            if !self.item_hash_set.remove(&id) {
+                self.item_hash_set.insert(id);
+            }
+        }
+    }
+
+    /// Marca todas as conexões da listagem completa com o mesmo valor de `field` da linha sob o
+    /// cursor, ou desmarca se todas já estavam marcadas.
+    pub fn select_matching(&mut self, field: Field, entries: &[Entry]) {
+        let Some(value) = self.selected_entry(entries).map(|e| field.value(e)) else { return };
+        let ids = self.all_entries
+            .iter()
+            .chain(entries)
+            .filter(|e| field.value(e) == value)
+            .map(|e| e.id.clone())
+            .collect();
+        self.toggle_all(ids);
+    }
+
+    /// Quantas das conexões em `targets` não aparecem nas linhas da tabela.
+    pub fn offscreen(&self, targets: &[AuditTarget], entries: &[Entry]) -> usize {
+        let rows = self.row_ids(entries);
+        targets.iter().filter(|t| !rows.iter().flatten().any(|id| *id == t.id)).count()
+    }
+
+    /// Quantidade de conexões marcadas, com as que não estão na tela, para o título da tabela.
+    fn selection_counter(&self, entries: &[Entry]) -> Option<String> {
+        let count = self.item_hash_set.len();
+        if count == 0 && !self.is_adding_selected {
+            return None;
+        }
+        let visible = self.row_ids(entries)
+            .into_iter()
+            .flatten()
+            .filter(|id| self.item_hash_set.contains(id))
+            .count();
+        let mut counter = format!("{} selecionada{}", count, if count == 1 { "" } else { "s" });
+        if count > visible {
+            counter.push_str(&format!(" ({} fora da tela)", count - visible));
+        }
+        if self.is_adding_selected {
+            counter.push_str(" [seleção contínua]");
+        }
+        Some(counter)
+    }
+
+    /// Com o filtro de duplicadas ativo, troca a página pelas sessões duplicadas da listagem completa.
+    /// Com texto na barra de filtro, troca pelas conexões da listagem completa (ou pelas duplicadas)
+    /// que contêm o texto em alguma coluna, sem diferenciar maiúsculas.
+    ///
+    /// A página volta na próxima vez que for buscada, então o filtro é aplicado antes de cada
+    /// renderização.
+    pub fn apply_filter(&self, entries: &mut Vec<Entry>) {
+        if self.duplicates_only {
+            *entries = self.duplicates.filter(&self.all_entries);
+        } else if !self.filter.is_empty() {
+            *entries = self.all_entries.clone();
+        }
+        if !self.filter.is_empty() {
+            entries.retain(|entry| self.matches_filter(entry));
+        }
+    }
+
+    /// A conexão contém o texto do filtro em alguma coluna.
+    fn matches_filter(&self, entry: &Entry) -> bool {
+        let query = self.filter.text().trim().to_lowercase();
+        Field::ALL.iter().any(|field| field.value(entry).to_lowercase().contains(&query))
+    }
+
+    /// Ordena a página pela coluna escolhida no cabeçalho, se houver.
+    pub fn apply_sort(&self, entries: &mut [Entry]) {
+        if let Some((field, ascending)) = self.sort {
+            entries.sort_by(|a, b| {
+                let order = field.compare(a, b);
+                if ascending { order } else { order.reverse() }
+            });
+        }
+    }
+
+    /// Linha da página sob a posição `y` do terminal, se houver.
+    fn row_at(&self, y: u16, entries: &[Entry]) -> Option<usize> {
+        let first = self.table_area.y + 2;
+        if y < first || y >= self.table_area.bottom().saturating_sub(1) {
+            return None;
+        }
+        let offset = match &self.grouping {
+            Some(grouping) => grouping.state.offset(),
+            None => self.table_state.offset(),
+        };
+        let index = offset + (y - first) as usize;
+        (index < self.row_count(entries)).then_some(index)
+    }
+
+    /// Depois de uma atualização, volta a selecionar a mesma conexão pelo ID.
+    ///
+    /// Se ela saiu da página, a seleção fica na mesma posição, limitada ao novo tamanho.
+    pub fn sync_selection(&mut self, entries: &[Entry]) {
+        let position = self.selected_id
+            .as_ref()
+            .and_then(|id| entries.iter().position(|e| &e.id == id));
+        match position {
+            Some(index) => self.select(index, entries),
+            None => self.select(self.selected_index(), entries),
+        }
+    }
     
 }
 
@@ -63,108 +424,309 @@ impl CliMonitor {
 /// # Retorno
 /// 
 /// Retorna um `Result` que indica se a renderizacao foi bem sucedida.
-pub fn render(monitor : &CliMonitor,entries: &Vec<Entry>, f: &mut Frame) -> Result<(), Box<dyn Error>> {
-    let size = f.area();
+pub fn render(monitor : &mut CliMonitor,entries: &[Entry], f: &mut Frame) -> Result<(), Box<dyn Error>> {
+    let mut size = f.area();
+    let theme = monitor.theme.clone();
+    // Com alertas ativos, a primeira linha da tela vira o aviso.
+    if let Some(banner) = monitor.alerts.banner(&theme) {
+        f.render_widget(ratatui::widgets::Paragraph::new(banner), Rect { height: 1, ..size });
+        size.y += 1;
+        size.height = size.height.saturating_sub(1);
+    }
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
-        .constraints([Constraint::Percentage(90), Constraint::Percentage(10)].as_ref())
+        .constraints([Constraint::Length(4), Constraint::Min(5), Constraint::Length(4)].as_ref())
         .split(size);
 
+    trends::draw_summary(f, &theme, chunks[0], &monitor.trends);
+
+    let table_area = if monitor.show_dashboard {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(68), Constraint::Percentage(32)].as_ref())
+            .split(chunks[1]);
+        trends::draw_dashboard(f, &theme, columns[1], &monitor.trends, monitor.listing_error.as_deref());
+        columns[0]
+    } else {
+        chunks[1]
+    };
+
     let header = Row::new(
-        ["usuario", "computador", "programa", "ambiente", "tempo de conexão", "tipo de conexão"]
+        monitor.columns
             .iter()
-            .map(|h| 
-                Cell::from(*h)
-                .style(
-                    Style::default()
-                    .fg(Color::White)
-                    .bg(Color::Black)
-                    .add_modifier(Modifier::BOLD)
-                )
+            .map(|c| 
+                Cell::from(match monitor.sort {
+                    Some((field, true)) if field == c.field => format!("{} ▲", c.field.label()),
+                    Some((field, false)) if field == c.field => format!("{} ▼", c.field.label()),
+                    _ => c.field.label().to_string(),
+                })
+                .style(theme.header().add_modifier(Modifier::BOLD))
             )
     );
                     
 
-    let rows = entries.iter().enumerate().map(|(i, row)| {
-        let row_strs = {
-            let mut row_strs = vec![];
-            row_strs.push(row.user_name.clone());
-            row_strs.push(row.machine_name.clone());
-            row_strs.push(row.function.clone());
-            row_strs.push(row.environment.clone());
-            row_strs.push(row.time_up.clone());
-            row_strs.push(row.thread_type.clone());
-            row_strs
-        };
-        let cells = row_strs.iter().map(|col| Cell::from(col.clone()));
-        
-        let mut styled_row = Row::new(cells);
-    
-        if i as i32 == monitor.selected {
-            styled_row = styled_row.style(Style::default().bg(Color::Gray).fg(Color::Black).add_modifier(Modifier::BOLD));
-        }else if monitor.item_hash_set.contains(&row.id.clone()){
-            styled_row = styled_row.style(Style::default().bg(Color::LightRed).fg(Color::Black).add_modifier(Modifier::BOLD));
+    if monitor.grouping.is_none() {
+        monitor.sync_selection(entries);
+    }
+    monitor.viewport_rows = table_area.height.saturating_sub(3) as usize;
+    monitor.frame_area = f.area();
+    monitor.table_area = table_area;
+    monitor.column_areas = Layout::horizontal(monitor.columns.iter().map(|c| Constraint::Percentage(c.width)))
+        .flex(Flex::Start)
+        .spacing(1)
+        .split(table_area.inner(Margin { vertical: 1, horizontal: 1 }))
+        .to_vec();
+
+    monitor.shown = if monitor.grouping.is_none() && !monitor.duplicates_only && monitor.filter.is_empty() {
+        entries.iter().map(|e| e.id.clone()).collect()
+    } else {
+        HashSet::new()
+    };
+
+    let rows: Vec<Row> = match &monitor.grouping {
+        Some(grouping) => grouped_rows(monitor, grouping),
+        None => {
+            let rows = entries.iter().map(|row| {
+                let styled_row = Row::new(row_cells(row, &monitor.columns, row_marker(monitor, row).as_deref()));
+                match row_style(monitor, &row.id) {
+                    Some(style) => styled_row.style(style),
+                    None => styled_row,
+                }
+            });
+
+            // Conexões que acabaram de sair ficam riscadas no fim da tabela até expirarem, mas só
+            // na página em que estavam ou, com filtro, se casam com ele. As demais entram só no
+            // contador do rodapé.
+            let paged = !monitor.duplicates_only && monitor.filter.is_empty();
+            let removed_rows = monitor.changes
+                .removed()
+                .filter(|(row, shown)| if paged { *shown } else { !monitor.duplicates_only && monitor.matches_filter(row) })
+                .map(|(row, _)| {
+                    Row::new(row_cells(row, &monitor.columns, None))
+                        .style(theme.removed())
+                });
+            rows.chain(removed_rows).collect()
         }
-    
-        styled_row
-    });
+    };
+    let row_count = rows.len();
+    let mut title = match &monitor.grouping {
+        Some(grouping) => format!("CLI Monitor - agrupado por {}", grouping.field.label()),
+        None if monitor.duplicates_only => format!("CLI Monitor - sessões duplicadas ({})", monitor.duplicates.len()),
+        None => "CLI Monitor".to_string(),
+    };
+    if let Some(counter) = monitor.selection_counter(entries) {
+        title = format!("{} - {}", title, counter);
+    }
+    let mut title = vec![Span::raw(title)];
+    if matches!(monitor.current_modal, Modal::Filter) && monitor.on_modal {
+        title.push(Span::raw(" - filtro: "));
+        title.extend(monitor.filter.spans(theme.accent()));
+    } else if !monitor.filter.is_empty() {
+        title.push(Span::raw(format!(" - filtro: {} ({})", monitor.filter.text(), entries.len())));
+    }
 
     let table = Table::new(
         rows,
-        [
-            Constraint::Percentage(16),
-            Constraint::Percentage(16),
-            Constraint::Percentage(16),
-            Constraint::Percentage(16),
-            Constraint::Percentage(18),
-            Constraint::Percentage(18),
-        ],
+        monitor.columns.iter().map(|c| Constraint::Percentage(c.width)),
     )
         .header(header)
-        .block(Block::default().title("CLI Monitor")
-        .border_style(Style::default().fg(Color::Blue))
+        .row_highlight_style(theme.selection())
+        .block(Block::default().title(Line::from(title))
+        .border_style(theme.border())
         .style(Style::default().add_modifier(Modifier::BOLD))
         .borders(Borders::ALL)
     )
         
         .column_spacing(1);
 
-    f.render_widget(table, chunks[0]);
+    let state = match &mut monitor.grouping {
+        Some(grouping) => &mut grouping.state,
+        None => &mut monitor.table_state,
+    };
+    f.render_stateful_widget(table, table_area, state);
 
-    let footer = Block::default()
-        .title("comandos")
-        .border_style(Style::default().fg(Color::Yellow))
+    let mut scrollbar_state = ScrollbarState::new(row_count).position(state.selected().unwrap_or(0));
+    f.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight),
+        table_area.inner(Margin { vertical: 1, horizontal: 0 }),
+        &mut scrollbar_state,
+    );
+
+    let counter = Line::from(vec![
+        Span::styled(format!("+{}", monitor.changes.last_added), theme.added_text()),
+        Span::raw(" / "),
+        Span::styled(format!("-{}", monitor.changes.last_removed), theme.error_text()),
+        Span::raw(" desde a última atualização"),
+    ]);
+    let mut footer = Block::default().title("comandos");
+    if !monitor.blocked.is_empty() {
+        let mode = if monitor.blocked.contains(&AuditAction::Message) {
+            " SOMENTE LEITURA - sem desconexões e mensagens "
+        } else {
+            " SOMENTE LEITURA - sem desconexões "
+        };
+        footer = footer.title(Span::styled(mode, theme.danger()));
+    }
+    let footer = footer
+        .title(counter.right_aligned())
+        .border_style(theme.footer_border())
         .borders(Borders::ALL);
+    // Folga para as quebras de linha, que não acontecem exatamente na borda.
+    let footer_len = (chunks[2].width.saturating_sub(2) as usize * 2).saturating_sub(chunks[2].width as usize / 4);
     f.render_widget(
-        ratatui::widgets::Paragraph::new("mudar de pagina < ← → > Sair <q>  Desconectar <d>  Mensagem <m>  Mais detalhes <M>  Atualizar <a> Des/Seleciona <e> Limpa seleção <E> seleciona varios <tab>")
+        ratatui::widgets::Paragraph::new(monitor.keymap.footer_text(footer_len))
             .block(footer)
             .style(
                 Style::default()
                     .add_modifier(Modifier::BOLD)
             )
             .wrap(Wrap { trim: true }),
-        chunks[1],
+        chunks[2],
     );
 
     Ok(())
 }
 
 
+const INPUT_POLL: Duration = Duration::from_millis(250);
+
+
+/// Células da linha; `marker` vai antes do valor da primeira coluna.
+fn row_cells<'a>(entry: &Entry, columns: &[ColumnConfig], marker: Option<&str>) -> Vec<Cell<'a>> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| match marker {
+            Some(marker) if i == 0 => Cell::from(format!("{} {}", marker, c.field.value(entry))),
+            _ => Cell::from(c.field.value(entry)),
+        })
+        .collect()
+}
+
+/// Marcadores antes da primeira coluna: cadeado das conexões protegidas e tipo de duplicada.
+fn row_marker(monitor: &CliMonitor, entry: &Entry) -> Option<String> {
+    let lock = protection::is_protected_entry(&monitor.protected, entry).then_some(protection::LOCK);
+    let duplicate = monitor.duplicates.kind(&entry.id).map(|d| d.marker());
+    match (lock, duplicate) {
+        (Some(lock), Some(duplicate)) => Some(format!("{}{}", lock, duplicate)),
+        (lock, duplicate) => lock.or(duplicate).map(str::to_string),
+    }
+}
+
+/// Destaque da linha: marcada para ações em lote, nova ou com salto de consumo.
+fn row_style(monitor: &CliMonitor, id: &str) -> Option<Style> {
+    let theme = &monitor.theme;
+    if monitor.item_hash_set.contains(id) {
+        Some(theme.marked())
+    } else if monitor.changes.is_new(id) {
+        Some(theme.added())
+    } else if monitor.changes.has_jumped(id) {
+        Some(theme.jumped())
+    } else {
+        None
+    }
+}
+
+/// Linhas da tabela agrupada.
+///
+/// O cabeçalho do grupo mostra a chave e a quantidade de conexões na primeira coluna e, nas
+/// demais, o valor agregado dos membros. As conexões dos grupos abertos vêm logo abaixo, recuadas.
+fn grouped_rows<'a>(monitor: &CliMonitor, grouping: &Grouping) -> Vec<Row<'a>> {
+    grouping.rows().into_iter().map(|row| match row {
+        GroupRow::Group(g) => {
+            let group = &grouping.groups[g];
+            let marker = if grouping.expanded.contains(&group.key) { "▾" } else { "▸" };
+            let cells: Vec<Cell> = monitor.columns.iter().enumerate().map(|(i, c)| {
+                if i == 0 {
+                    Cell::from(format!("{} {} ({})", marker, group.key, group.members.len()))
+                } else {
+                    Cell::from(group.aggregate(c.field))
+                }
+            }).collect();
+            let all_marked = group.members.iter().all(|e| monitor.item_hash_set.contains(&e.id));
+            let style = if all_marked { monitor.theme.marked() } else { Style::default().add_modifier(Modifier::BOLD) };
+            Row::new(cells).style(style)
+        }
+        GroupRow::Member(g, m) => {
+            let entry = &grouping.groups[g].members[m];
+            let cells: Vec<Cell> = monitor.columns.iter().enumerate().map(|(i, c)| {
+                let value = c.field.value(entry);
+                match row_marker(monitor, entry) {
+                    Some(marker) if i == 0 => Cell::from(format!(" {} {}", marker, value)),
+                    _ if i == 0 => Cell::from(format!("   {}", value)),
+                    _ => Cell::from(value),
+                }
+            }).collect();
+            let row = Row::new(cells);
+            match row_style(monitor, &entry.id) {
+                Some(style) => row.style(style),
+                None => row,
+            }
+        }
+    }).collect()
+}
+
+
 /// Returns a vector of the selected entries' hashes.
 /// 
 /// If there are no selected entries, it will return a vector with the hash of the currently selected entry.
-pub fn selected_hashs_to_vec(monitor : &CliMonitor, entries: &Vec<Entry>) -> Vec<String> {
+pub fn selected_hashs_to_vec(monitor : &CliMonitor, entries: &[Entry]) -> Vec<String> {
     let mut hash_vec: Vec<String> = Vec::new();
     for hash in monitor.item_hash_set.iter(){
         hash_vec.push(hash.to_string());
     }
-    if hash_vec.len() == 0 {
-        hash_vec.push(entries[monitor.selected as usize].id.clone());
+    if hash_vec.is_empty() {
+        match (monitor.selected_entry(entries), &monitor.grouping) {
+            (Some(entry), _) => hash_vec.push(entry.id.clone()),
+            (None, Some(grouping)) => hash_vec.extend(grouping.current_group().map(|g| g.ids()).unwrap_or_default()),
+            (None, None) => {}
+        }
     }
     hash_vec
 }
 
+/// Returns snapshots of the selected entries, in the same order as `selected_hashs_to_vec`.
+///
+/// IDs that are neither on the current page nor in the full listing are kept with only their ID filled in.
+///
+/// While the detail view, or a modal opened from it, is showing, the target is the connection in it.
+pub fn selected_targets(monitor : &CliMonitor, entries: &[Entry]) -> Vec<AuditTarget> {
+    if let Some(detail) = &monitor.detail
+        && monitor.on_modal
+        && matches!(monitor.current_modal, Modal::Detail | Modal::Delete | Modal::SendMsg)
+    {
+        return vec![AuditTarget::from_entry(&detail.entry)];
+    }
+    selected_hashs_to_vec(monitor, entries)
+        .iter()
+        .map(|id| match entries.iter().chain(&monitor.all_entries).find(|e| &e.id == id) {
+            Some(entry) => AuditTarget::from_entry(entry),
+            None => AuditTarget::unknown(id),
+        })
+        .collect()
+}
+
+/// Returns snapshots of the marked entries only, sorted by user, machine and program, for the review list.
+pub fn marked_targets(monitor : &CliMonitor, entries: &[Entry]) -> Vec<AuditTarget> {
+    let mut targets: Vec<AuditTarget> = monitor.item_hash_set
+        .iter()
+        .map(|id| match entries.iter().chain(&monitor.all_entries).find(|e| &e.id == id) {
+            Some(entry) => AuditTarget::from_entry(entry),
+            None => AuditTarget::unknown(id),
+        })
+        .collect();
+    sort_targets(&mut targets);
+    targets
+}
+
+/// Orders targets by user, machine and program so lists shown before an action are easy to scan.
+pub fn sort_targets(targets: &mut [AuditTarget]) {
+    targets.sort_by(|a, b| {
+        (&a.user_name, &a.machine_name, &a.function, &a.id).cmp(&(&b.user_name, &b.machine_name, &b.function, &b.id))
+    });
+}
+
 /// Handle user input
 ///
 /// This function will handle all the user input. If the user is on a modal, it will handle the modal's input.
@@ -173,42 +735,170 @@ pub fn selected_hashs_to_vec(monitor : &CliMonitor, entries: &Vec<Entry>) -> Vec
 /// The function will return `true` if the user wants to quit and `false` otherwise. If the user wants to quit and there is an error, the function will return an error.
 ///
 /// The function will also update the table if the user goes to another page or updates the table.
+///
+/// Waits at most `INPUT_POLL` for a key, so the caller can process timer events in between.
 pub async fn user_key_input(
     monitor : &mut CliMonitor, 
     entries: &mut Vec<Entry>, 
     page: &mut i32, 
     token: &str, 
     client: &Client, 
-    config: &config::Config, 
-    input_buffer: &mut String,
+    config: &mut config::Config, 
+    composer: &mut Composer,
 ) -> Result<bool, MonitorError> {
+    if !event::poll(INPUT_POLL).unwrap_or(false) {
+        return Ok(false);
+    }
+
     match event::read(){
         Ok(Event::Key(key)) => {
             if monitor.on_modal {
         
                 match monitor.current_modal {
                     Modal::Delete => {
-                        let items  = selected_hashs_to_vec(monitor,entries);
+                        let items  = selected_targets(monitor,entries);
                         
-                        monitor.on_modal = modal::confirm_del_modal(&key, &items, &token, &client, &config).await;
+                        let result = modal::confirm_del_modal(&key, &monitor.keymap, &items, &mut monitor.confirm_input, token, client, config).await;
+                        monitor.on_modal = matches!(result, Ok(true));
+                        if !monitor.on_modal {
+                            monitor.restore_selection();
+                        }
+                        if let Err(e) = result {
+                            monitor.detail = None;
+                            return Err(e);
+                        }
                     }
                     Modal::Info => {
-                        monitor.on_modal = modal::more_info_keys(&key).await;
+                        if key.code == KeyCode::Enter {
+                            monitor.detail = monitor.selected_entry(entries).map(Detail::new);
+                            if monitor.detail.is_some() {
+                                monitor.set_modal(Modal::Detail);
+                            }
+                        } else {
+                            monitor.on_modal = modal::more_info_keys(&key).await;
+                        }
+                    }
+                    Modal::Detail => {
+                        match (key.code, monitor.keymap.action(&key, Context::Table)) {
+                            (KeyCode::Esc | KeyCode::Char('q'), _) => {
+                                monitor.on_modal = false;
+                                monitor.detail = None;
+                            }
+                            (_, Some(Action::Delete)) => monitor.set_modal(Modal::Delete),
+                            (_, Some(Action::Message)) => monitor.set_modal(Modal::SendMsg),
+                            (_, Some(Action::Refresh)) => refresh_listing(monitor, config, token, client).await?,
+                            _ => {}
+                        }
                     }
                     Modal::SendMsg => {
-                        let items  = selected_hashs_to_vec(monitor,entries);
-                        match modal::message_keys(&key, input_buffer, &items, token, &client, &config).await{
+                        let items  = selected_targets(monitor,entries);
+                        match modal::message_keys(&key, composer, &items, token, client, config).await{
                             Ok(b) => monitor.on_modal = b,
                             Err(e) => {
                                 monitor.on_modal = false;
+                                monitor.detail = None;
                                 return Err(e);
                             }
                         };
                     }
+                    Modal::Help => {
+                        monitor.on_modal = modal::audit_keys(&key, &mut monitor.help_scroll, modal::help_lines(&monitor.keymap, &monitor.theme).len())
+                            && monitor.keymap.action(&key, Context::Table) != Some(Action::Help);
+                    }
+                    Modal::Palette => {
+                        match modal::palette_keys(&key, &mut monitor.palette) {
+                            Some(Some(action)) => {
+                                monitor.on_modal = false;
+                                if run_action(monitor, action, entries, page, token, client, config).await? {
+                                    return Ok(true);
+                                }
+                            }
+                            Some(None) => monitor.on_modal = false,
+                            None => {}
+                        }
+                    }
+                    Modal::Review => {
+                        let targets = marked_targets(monitor, entries);
+                        match (key.code, monitor.keymap.action(&key, Context::Table)) {
+                            (KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter, _) => monitor.on_modal = false,
+                            (KeyCode::Up, _) => monitor.review_cursor = monitor.review_cursor.saturating_sub(1),
+                            (KeyCode::Down, _) if monitor.review_cursor + 1 < targets.len() => monitor.review_cursor += 1,
+                            (KeyCode::Char(' ') | KeyCode::Delete, _) | (_, Some(Action::ToggleSelect)) => {
+                                if let Some(target) = targets.get(monitor.review_cursor) {
+                                    monitor.item_hash_set.remove(&target.id);
+                                }
+                                monitor.review_cursor = monitor.review_cursor.min(targets.len().saturating_sub(2));
+                                monitor.on_modal = !monitor.item_hash_set.is_empty();
+                            }
+                            (_, Some(Action::ClearSelection)) => {
+                                monitor.item_hash_set.clear();
+                                monitor.on_modal = false;
+                            }
+                            (_, Some(Action::Delete)) => monitor.set_modal(Modal::Delete),
+                            (_, Some(Action::Message)) => monitor.set_modal(Modal::SendMsg),
+                            _ => {}
+                        }
+                    }
+                    Modal::Filter => {
+                        match key.code {
+                            KeyCode::Enter => monitor.on_modal = false,
+                            KeyCode::Esc => {
+                                monitor.filter.clear();
+                                monitor.on_modal = false;
+                            }
+                            _ => {
+                                monitor.filter.handle(&key);
+                            }
+                        }
+                    }
+                    Modal::Audit => {
+                        let len = monitor.audit_records.len();
+                        monitor.on_modal = modal::audit_keys(&key, &mut monitor.audit_scroll, len);
+                    }
+                    Modal::Alerts => {
+                        let len = monitor.alerts.history.len();
+                        monitor.on_modal = modal::audit_keys(&key, &mut monitor.alerts.scroll, len);
+                    }
+                    Modal::Export => match key.code {
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => monitor.on_modal = false,
+                        KeyCode::Tab => monitor.export.full = !monitor.export.full,
+                        KeyCode::Char(c) => {
+                            if let Some(format) = ExportFormat::ALL.into_iter().find(|f| f.key() == c) {
+                                export_entries(monitor, entries, config, format)?;
+                            }
+                        }
+                        _ => {}
+                    },
+                    Modal::Context => {
+                        match modal::context_menu_keys(&key, &mut monitor.context_menu) {
+                            Some(Some(modal)) => monitor.set_modal(modal),
+                            Some(None) => monitor.on_modal = false,
+                            None => {}
+                        }
+                    }
+                    Modal::Columns => {
+                        match modal::column_picker_keys(&key, &mut monitor.column_picker) {
+                            modal::PickerAction::Stay => {}
+                            modal::PickerAction::Cancel => monitor.on_modal = false,
+                            modal::PickerAction::Save => {
+                                monitor.on_modal = false;
+                                monitor.columns = monitor.column_picker.columns();
+                                config.columns = monitor.columns.clone();
+                                config::save_columns(&config.columns).map_err(|e| MonitorError::ConfigError(e.to_string()))?;
+                            }
+                        }
+                    }
                     Modal::None => {}
                 }
+
+                // Desconectar e enviar mensagem pela tela de detalhes voltam para ela.
+                if !monitor.on_modal && monitor.detail.is_some() && matches!(monitor.current_modal, Modal::Delete | Modal::SendMsg) {
+                    monitor.set_modal(Modal::Detail);
+                }
                 
-                update(config,&token, &client, *page,  entries).await;
+                if !monitor.on_modal {
+                    update(config,token, client, *page,  entries).await;
+                }
             }else if monitor.is_on_error {
                 
                 match key.code {
@@ -222,93 +912,293 @@ pub async fn user_key_input(
                 }
             
             } else {
-                let entrie_selected = &entries[monitor.selected as usize];
-                match key.code {
-                    KeyCode::Char('q') => return Ok(true),
-                    KeyCode::Down => {
-                        monitor.selected = (monitor.selected + 1) % entries.len() as i32;
-                        
-                        if monitor.is_adding_selected{
-                            let contains = monitor.item_hash_set.contains(&entrie_selected.id.clone());
-                            if contains {
-                                monitor.item_hash_set.remove(&entrie_selected.id.clone());
-                            }else{
-                                monitor.item_hash_set.insert(entrie_selected.id.clone());
-                            }
+                match monitor.keymap.action(&key, Context::Table) {
+                    Some(action) => return run_action(monitor, action, entries, page, token, client, config).await,
+                    None => match key.code {
+                        KeyCode::PageDown => {
+                            monitor.move_selection(monitor.viewport_rows.max(1) as isize, false, entries);
                         }
-                    }
-                    KeyCode::Up => {
-                        if monitor.selected > 0 {
-                            monitor.selected -= 1;
-                        } else {
-                            monitor.selected = entries.len() as i32 - 1;
+                        KeyCode::PageUp => {
+                            monitor.move_selection(-(monitor.viewport_rows.max(1) as isize), false, entries);
                         }
-
-                        if monitor.is_adding_selected{
-                            let contains = monitor.item_hash_set.contains(&entrie_selected.id.clone());
-                            if contains {
-                                monitor.item_hash_set.remove(&entrie_selected.id.clone());
-                            }else{
-                                monitor.item_hash_set.insert(entrie_selected.id.clone());
-                            }
+                        KeyCode::Home => {
+                            monitor.select(0, entries);
                         }
-                    }
-                    KeyCode::Right => {
-                        if *page < i32::MAX{
-                            *page += 1;
-                            update(config,&token, &client, *page,  entries).await;
-                        }else{
-                            *page = 0
-                        }
-                    }
-                    KeyCode::Left => {
-                        if *page > 0 { 
-                            *page -= 1;
-                            update(config,&token, &client, *page,  entries).await;
+                        KeyCode::End => {
+                            monitor.select(monitor.row_count(entries).saturating_sub(1), entries);
                         }
+                        _ => {}
                     },
-                    KeyCode::Char('a') => {
-                        update(config,&token, &client, *page,  entries).await;
-                    }
-                    KeyCode::Char('d') => {
-                        monitor.set_modal(Modal::Delete);
-                    }
-                    KeyCode::Char('m') => {
-                        monitor.set_modal(Modal::SendMsg);
-                    }
-                    KeyCode::Char('M') => {
-                        monitor.set_modal(Modal::Info);
-                    }
-                    KeyCode::Tab => {
-                        if monitor.is_adding_selected{
-                            monitor.item_hash_set.insert(entrie_selected.id.clone());
-                        }else{
-                            monitor.item_hash_set.clear();
-                        }
-                        
-                        monitor.is_adding_selected = !monitor.is_adding_selected;
-                    }
-                    
-                    KeyCode::Char('e') =>{
-                        let contains = monitor.item_hash_set.contains(&entrie_selected.id.clone());
-                        if contains {
-                            monitor.item_hash_set.remove(&entrie_selected.id.clone());
-                        }else{
-                            monitor.item_hash_set.insert(entrie_selected.id.clone());
-                        }
+                }
+            }
+        }
+        Ok(Event::Mouse(mouse)) => mouse_input(monitor, entries, mouse),
+        Ok(Event::Paste(text)) if monitor.on_modal => match monitor.current_modal {
+            Modal::SendMsg => composer.input.insert_str(&text),
+            Modal::Filter => monitor.filter.insert_str(&text),
+            Modal::Delete => monitor.confirm_input.insert_str(&text),
+            _ => {}
+        },
+        Err(e) => panic!("Error: {}", e),
+        _ => {}
+    }
+    Ok(false)
+}
+
+
+/// Executa uma ação da tabela, vinda de uma tecla ou da paleta de comandos.
+///
+/// Retorna `true` se o usuário pediu para sair.
+async fn run_action(
+    monitor : &mut CliMonitor,
+    action: Action,
+    entries: &mut Vec<Entry>,
+    page: &mut i32,
+    token: &str,
+    client: &Client,
+    config: &mut config::Config,
+) -> Result<bool, MonitorError> {
+    let selected_id = monitor.selected_entry(entries).map(|e| e.id.clone());
+    if !matches!(action, Action::ExtendUp | Action::ExtendDown) {
+        monitor.range_anchor = None;
+        monitor.range_ids.clear();
+    }
+    match action {
+        Action::Quit => return Ok(true),
+        action @ (Action::Down | Action::Up) => {
+            monitor.move_selection(if action == Action::Down { 1 } else { -1 }, true, entries);
+
+            // No modo de seleção contínua, a linha onde o cursor chega é marcada.
+            if monitor.is_adding_selected
+                && let Some(id) = monitor.selected_entry(entries).map(|e| e.id.clone())
+            {
+                monitor.item_hash_set.insert(id);
+            }
+        }
+        action @ (Action::ExtendDown | Action::ExtendUp) => {
+            monitor.extend_selection(if action == Action::ExtendDown { 1 } else { -1 }, entries);
+        }
+        Action::SelectAll => monitor.select_visible(entries),
+        Action::InvertSelection => monitor.invert_selection(entries),
+        Action::SelectUser => monitor.select_matching(Field::UserName, entries),
+        Action::SelectProgram => monitor.select_matching(Field::Function, entries),
+        Action::ReviewSelection => {
+            if !monitor.item_hash_set.is_empty() {
+                monitor.review_cursor = 0;
+                monitor.set_modal(Modal::Review);
+            }
+        }
+        Action::NextPage => {
+            monitor.changes.leave_page();
+            if *page < i32::MAX{
+                *page += 1;
+                update(config,token, client, *page,  entries).await;
+            }else{
+                *page = 0
+            }
+        }
+        Action::PrevPage => {
+            if *page > 0 {
+                monitor.changes.leave_page();
+                *page -= 1;
+                update(config,token, client, *page,  entries).await;
+            }
+        }
+        Action::Refresh => {
+            update(config,token, client, *page,  entries).await;
+            refresh_listing(monitor, config, token, client).await?;
+        }
+        Action::Columns => {
+            monitor.column_picker = ColumnPicker::new(&monitor.columns);
+            monitor.set_modal(Modal::Columns);
+        }
+        Action::Dashboard => {
+            monitor.show_dashboard = !monitor.show_dashboard;
+        }
+        // Sem linha marcada nem linha sob o cursor não há o que desconectar.
+        Action::Delete if selected_targets(monitor, entries).is_empty() => {}
+        Action::Delete => {
+            monitor.set_modal(Modal::Delete);
+        }
+        Action::Message => {
+            monitor.set_modal(Modal::SendMsg);
+        }
+        Action::Details => {
+            monitor.set_modal(Modal::Info);
+        }
+        Action::Detail if matches!(monitor.grouping.as_ref().and_then(|g| g.current()), Some(GroupRow::Group(_))) => {
+            if let Some(grouping) = &mut monitor.grouping {
+                grouping.toggle_expand();
+            }
+        }
+        Action::Detail => {
+            monitor.detail = monitor.selected_entry(entries).map(Detail::new);
+            if monitor.detail.is_some() {
+                monitor.set_modal(Modal::Detail);
+            }
+        }
+        Action::Help => {
+            monitor.help_scroll = 0;
+            monitor.set_modal(Modal::Help);
+        }
+        Action::Audit => {
+            let query = AuditQuery { limit: Some(200), ..Default::default() };
+            monitor.audit_records = audit::read_records(&config.audit_file, &query)
+                .map_err(|e| MonitorError::AuditError(e.to_string()))?;
+            monitor.audit_scroll = 0;
+            monitor.set_modal(Modal::Audit);
+        }
+        Action::Export => {
+            monitor.export.last = None;
+            monitor.set_modal(Modal::Export);
+        }
+        Action::Alerts => {
+            monitor.alerts.acknowledge();
+            monitor.alerts.scroll = 0;
+            monitor.set_modal(Modal::Alerts);
+        }
+        // Ligar a seleção contínua marca a linha atual; desligar mantém o que já foi marcado.
+        Action::MultiSelect => {
+            monitor.is_adding_selected = !monitor.is_adding_selected;
+            if monitor.is_adding_selected
+                && let Some(id) = selected_id
+            {
+                monitor.item_hash_set.insert(id);
+            }
+        }
+        Action::ToggleSelect =>{
+            match (selected_id, &monitor.grouping) {
+                (Some(id), _) => {
+                    if !monitor.item_hash_set.remove(&id) {
+                        monitor.item_hash_set.insert(id);
                     }
-                    KeyCode::Char('E') =>{
-                        monitor.item_hash_set.clear();
+                }
+                // No cabeçalho de um grupo, marca todas as conexões dele, ou desmarca se já estavam marcadas.
+                (None, Some(grouping)) => {
+                    let ids = grouping.current_group().map(|g| g.ids()).unwrap_or_default();
+                    monitor.toggle_all(ids);
+                }
+                (None, None) => {}
+            }
+        }
+        Action::Duplicates => {
+            monitor.duplicates_only = !monitor.duplicates_only;
+            if !monitor.duplicates_only {
+                update(config, token, client, *page, entries).await;
+            }
+        }
+        Action::Filter => {
+            monitor.set_modal(Modal::Filter);
+        }
+        Action::KillDuplicates => {
+            // Sessões protegidas ficam de fora; para derrubá-las, marque-as e desconecte pela confirmação.
+            let older: HashSet<String> = monitor.duplicates
+                .older()
+                .iter()
+                .filter(|id| !monitor.all_entries.iter().any(|e| &e.id == *id && protection::is_protected_entry(&monitor.protected, e)))
+                .cloned()
+                .collect();
+            if !older.is_empty() {
+                monitor.saved_selection = Some(std::mem::replace(&mut monitor.item_hash_set, older));
+                monitor.set_modal(Modal::Delete);
+                if !monitor.on_modal {
+                    monitor.restore_selection();
+                }
+            }
+        }
+        Action::GroupBy => {
+            let field = grouping::next_field(monitor.grouping.as_ref().map(|g| g.field));
+            monitor.grouping = field.map(|field| Grouping::new(field, &monitor.all_entries));
+        }
+        Action::ClearSelection =>{
+            monitor.item_hash_set.clear();
+        }
+        Action::Palette => {
+            monitor.palette = Palette::default();
+            monitor.set_modal(Modal::Palette);
+        }
+        Action::Confirm | Action::Cancel => {}
+    }
+    Ok(false)
+}
+
+
+/// Intervalo máximo entre dois cliques na mesma linha para contar como duplo clique.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Handle mouse input
+///
+/// On the table, a left click selects the row (a double click opens the details), a click on the
+/// header sorts by that column, the wheel scrolls and a right click opens the context menu.
+/// While the context menu is open, clicking an option picks it and clicking outside closes it.
+fn mouse_input(monitor : &mut CliMonitor, entries: &[Entry], mouse: MouseEvent) {
+    if monitor.is_on_error {
+        return;
+    }
+
+    if monitor.on_modal {
+        match monitor.current_modal {
+            Modal::Context => {
+                if let MouseEventKind::Down(_) = mouse.kind {
+                    match monitor.context_menu.item_at(mouse.column, mouse.row, monitor.frame_area) {
+                        Some(modal) => monitor.set_modal(modal),
+                        None => monitor.on_modal = false,
                     }
+                }
+            }
+            Modal::Audit => match mouse.kind {
+                MouseEventKind::ScrollDown if monitor.audit_scroll + 1 < monitor.audit_records.len() => monitor.audit_scroll += 1,
+                MouseEventKind::ScrollUp => monitor.audit_scroll = monitor.audit_scroll.saturating_sub(1),
+                _ => {}
+            },
+            Modal::Alerts => match mouse.kind {
+                MouseEventKind::ScrollDown if monitor.alerts.scroll + 1 < monitor.alerts.history.len() => monitor.alerts.scroll += 1,
+                MouseEventKind::ScrollUp => monitor.alerts.scroll = monitor.alerts.scroll.saturating_sub(1),
+                _ => {}
+            },
+            _ => {}
+        }
+        return;
+    }
 
-                    _ => {}
+    match mouse.kind {
+        MouseEventKind::ScrollDown => monitor.move_selection(1, false, entries),
+        MouseEventKind::ScrollUp => monitor.move_selection(-1, false, entries),
+        MouseEventKind::Down(MouseButton::Left) => {
+            if mouse.row == monitor.table_area.y + 1 {
+                let column = monitor.column_areas
+                    .iter()
+                    .position(|area| mouse.column >= area.x && mouse.column < area.right());
+                if let Some(column) = column {
+                    let field = monitor.columns[column].field;
+                    monitor.sort = match monitor.sort {
+                        Some((current, true)) if current == field => Some((field, false)),
+                        Some((current, false)) if current == field => None,
+                        _ => Some((field, true)),
+                    };
                 }
+                return;
+            }
+
+            let Some(index) = monitor.row_at(mouse.row, entries) else { return };
+            monitor.select(index, entries);
+
+            let double = monitor.last_click.is_some_and(|(at, row)| row == index && at.elapsed() < DOUBLE_CLICK);
+            if double {
+                monitor.last_click = None;
+                monitor.set_modal(Modal::Info);
+            } else {
+                monitor.last_click = Some((Instant::now(), index));
             }
         }
-        Err(e) => panic!("Error: {}", e),
+        MouseEventKind::Down(MouseButton::Right) => {
+            let Some(index) = monitor.row_at(mouse.row, entries) else { return };
+            monitor.select(index, entries);
+            monitor.context_menu = modal::ContextMenu::at(mouse.column, mouse.row);
+            monitor.set_modal(Modal::Context);
+        }
         _ => {}
     }
-    Ok(false)
 }
 
 
@@ -329,7 +1219,7 @@ pub async fn update(
     page: i32,
     entries: &mut Vec<Entry>,
 ){
-    *entries = match api_service::get_entries(config,token, client, page, 10).await{
+    *entries = match api_service::get_entries(config,token, client, page, config.page_size).await{
         Ok(e) => e,
         Err(e) => {
             println!("Error: {}", e);
@@ -337,3 +1227,78 @@ pub async fn update(
         }
     };
 }
+
+
+/// Exports the table view, or the full listing when `monitor.export.full` is set, with the
+/// visible columns and the table sort.
+fn export_entries(monitor: &mut CliMonitor, entries: &[Entry], config: &config::Config, format: ExportFormat) -> Result<(), MonitorError> {
+    let fields: Vec<Field> = monitor.columns.iter().map(|c| c.field).collect();
+    let result = if monitor.export.full {
+        let mut all = monitor.all_entries.clone();
+        monitor.apply_sort(&mut all);
+        export::export(config, &all, &fields, "listagem completa", format)
+    } else {
+        export::export(config, entries, &fields, "visão atual", format)
+    };
+    match result {
+        Ok(path) => {
+            monitor.export.last = Some(path);
+            Ok(())
+        }
+        Err(e) => {
+            monitor.on_modal = false;
+            Err(MonitorError::ExportError(e.to_string()))
+        }
+    }
+}
+
+
+/// Fetches the full listing of connections, feeding the trend dashboard and the history.
+///
+/// When the listing can't be fetched the previous one is kept and the error is shown in the
+/// dashboard, so a server that is down doesn't open a modal on every refresh.
+///
+/// # Arguments
+///
+/// * `monitor`: The monitor state that holds the full listing
+/// * `config`: The configuration with the `[history]` section
+/// * `token`: The token to use for the api request
+/// * `client`: The client to use for the api request
+pub async fn refresh_listing(
+    monitor: &mut CliMonitor,
+    config: &config::Config,
+    token: &str,
+    client: &Client,
+) -> Result<(), MonitorError> {
+    match api_service::get_all_entries(config, token, client).await {
+        Ok(all_entries) => {
+            monitor.all_entries = all_entries;
+            monitor.listing_error = None;
+            monitor.hook_watch.listing(config, &monitor.all_entries);
+            monitor.alerts.evaluate(&config.alerts, Ok(&monitor.all_entries));
+        }
+        Err(e) => {
+            monitor.listing_error = Some(e.to_string());
+            monitor.hook_watch.failure(config, &e.to_string());
+            monitor.alerts.evaluate(&config.alerts, Err(&e.to_string()));
+            return Ok(());
+        }
+    };
+
+    // Conexões marcadas que saíram da listagem não podem mais ser desconectadas nem receber mensagem.
+    monitor.item_hash_set.retain(|id| monitor.all_entries.iter().any(|e| &e.id == id));
+    monitor.trends.push(&monitor.all_entries);
+    monitor.duplicates = Duplicates::find(&monitor.all_entries);
+    if let Some(detail) = &mut monitor.detail {
+        detail.update(&monitor.all_entries);
+    }
+    if let Some(grouping) = &mut monitor.grouping {
+        grouping.update(&monitor.all_entries);
+    }
+    monitor.changes.update(&monitor.all_entries, &config.highlight, &monitor.shown);
+
+    if config.history.enabled {
+        history::record(&config.history, &monitor.all_entries).map_err(|e| MonitorError::HistoryError(e.to_string()))?;
+    }
+    Ok(())
+}
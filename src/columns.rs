@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{api_service::Entry, trends::format_bytes};
+
+
+/// Campos do `Entry` que podem ser exibidos como colunas da tabela.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    UserName,
+    MachineName,
+    Function,
+    Environment,
+    TimeUp,
+    ThreadType,
+    Server,
+    ThreadId,
+    Memory,
+    Instructions,
+    InstructionsPs,
+    InactiveTime,
+    DateTime,
+    Comments,
+    SId,
+    IdCtree,
+}
+
+impl Field {
+    pub const ALL: [Field; 16] = [
+        Field::UserName,
+        Field::MachineName,
+        Field::Function,
+        Field::Environment,
+        Field::TimeUp,
+        Field::ThreadType,
+        Field::Server,
+        Field::ThreadId,
+        Field::Memory,
+        Field::Instructions,
+        Field::InstructionsPs,
+        Field::InactiveTime,
+        Field::DateTime,
+        Field::Comments,
+        Field::SId,
+        Field::IdCtree,
+    ];
+
+    /// Título da coluna na tabela.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::UserName => "usuario",
+            Field::MachineName => "computador",
+            Field::Function => "programa",
+            Field::Environment => "ambiente",
+            Field::TimeUp => "tempo de conexão",
+            Field::ThreadType => "tipo de conexão",
+            Field::Server => "servidor",
+            Field::ThreadId => "thread",
+            Field::Memory => "memória",
+            Field::Instructions => "instruções",
+            Field::InstructionsPs => "instruções/s",
+            Field::InactiveTime => "tempo inativo",
+            Field::DateTime => "data/hora",
+            Field::Comments => "comentários",
+            Field::SId => "sid",
+            Field::IdCtree => "ctree",
+        }
+    }
+
+    /// Compara duas conexões por este campo, numericamente quando o campo é um número ou tempo.
+    pub fn compare(&self, a: &Entry, b: &Entry) -> Ordering {
+        match self {
+            Field::ThreadId => a.thread_id.cmp(&b.thread_id),
+            Field::Memory => a.memory.cmp(&b.memory),
+            Field::Instructions => a.instructions.cmp(&b.instructions),
+            Field::InstructionsPs => a.instructions_ps.cmp(&b.instructions_ps),
+            Field::IdCtree => a.id_ctree.cmp(&b.id_ctree),
+            Field::InactiveTime => a.inactive_secs().cmp(&b.inactive_secs()),
+            Field::TimeUp => a.time_up_secs().cmp(&b.time_up_secs()),
+            _ => self.value(a).to_lowercase().cmp(&self.value(b).to_lowercase()),
+        }
+    }
+
+    /// Valor do campo formatado para exibição.
+    pub fn value(&self, entry: &Entry) -> String {
+        match self {
+            Field::UserName => entry.user_name.trim().to_string(),
+            Field::MachineName => entry.machine_name.trim().to_string(),
+            Field::Function => entry.function.trim().to_string(),
+            Field::Environment => entry.environment.trim().to_string(),
+            Field::TimeUp => entry.time_up.trim().to_string(),
+            Field::ThreadType => entry.thread_type.trim().to_string(),
+            Field::Server => entry.server.trim().to_string(),
+            Field::ThreadId => entry.thread_id.to_string(),
+            Field::Memory => format_bytes(entry.memory.max(0) as u64),
+            Field::Instructions => entry.instructions.to_string(),
+            Field::InstructionsPs => entry.instructions_ps.to_string(),
+            Field::InactiveTime => entry.inactive_time.trim().to_string(),
+            Field::DateTime => entry.date_time.trim().to_string(),
+            Field::Comments => entry.comments.trim().to_string(),
+            Field::SId => entry.s_id.trim().to_string(),
+            Field::IdCtree => entry.id_ctree.to_string(),
+        }
+    }
+}
+
+
+/// Uma coluna visível da tabela, com a largura em porcentagem.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ColumnConfig {
+    pub field: Field,
+    pub width: u16,
+}
+
+/// Layout original da tabela, usado quando o config.toml não tem `[[columns]]`.
+pub fn default_columns() -> Vec<ColumnConfig> {
+    [
+        (Field::UserName, 16),
+        (Field::MachineName, 16),
+        (Field::Function, 16),
+        (Field::Environment, 16),
+        (Field::TimeUp, 18),
+        (Field::ThreadType, 18),
+    ]
+    .into_iter()
+    .map(|(field, width)| ColumnConfig { field, width })
+    .collect()
+}
+
+
+/// Estado do modal de escolha de colunas.
+///
+/// Lista todos os campos, os visíveis primeiro e na ordem em que aparecem na tabela.
+pub struct ColumnPicker {
+    pub items: Vec<(Field, u16, bool)>,
+    pub cursor: usize,
+}
+
+impl ColumnPicker {
+    pub fn new(columns: &[ColumnConfig]) -> Self {
+        let mut items: Vec<(Field, u16, bool)> = columns.iter().map(|c| (c.field, c.width, true)).collect();
+        for field in Field::ALL {
+            if !columns.iter().any(|c| c.field == field) {
+                items.push((field, 12, false));
+            }
+        }
+        Self { items, cursor: 0 }
+    }
+
+    pub fn toggle(&mut self) {
+        let visible = self.items.iter().filter(|i| i.2).count();
+        let item = &mut self.items[self.cursor];
+        // Sempre sobra ao menos uma coluna visível.
+        if !item.2 || visible > 1 {
+            item.2 = !item.2;
+        }
+    }
+
+    pub fn move_cursor(&mut self, down: bool) {
+        let len = self.items.len();
+        self.cursor = if down { (self.cursor + 1) % len } else { (self.cursor + len - 1) % len };
+    }
+
+    /// Move o campo sob o cursor uma posição para cima ou para baixo.
+    pub fn reorder(&mut self, down: bool) {
+        let target = if down { self.cursor + 1 } else { self.cursor.wrapping_sub(1) };
+        if target < self.items.len() {
+            self.items.swap(self.cursor, target);
+            self.cursor = target;
+        }
+    }
+
+    pub fn resize(&mut self, delta: i16) {
+        let item = &mut self.items[self.cursor];
+        item.1 = (item.1 as i16 + delta).clamp(2, 100) as u16;
+    }
+
+    pub fn columns(&self) -> Vec<ColumnConfig> {
+        self.items
+            .iter()
+            .filter(|i| i.2)
+            .map(|(field, width, _)| ColumnConfig { field: *field, width: *width })
+            .collect()
+    }
+}
@@ -1,9 +1,11 @@
-use crossterm::{cursor, event::{read, Event, KeyCode}, terminal::{disable_raw_mode, enable_raw_mode}, ExecutableCommand};
+use ratatui::crossterm::{cursor, event::{read, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode}, terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType}, ExecutableCommand};
 use serde::{Deserialize, Serialize};
-use std::{fs, io::{stdout, Write}};
-use std::io::stdin;
+use std::{collections::BTreeMap, fs, io::{stdout, Write}};
+use toml_edit::DocumentMut;
 
-use crate::errors::ConfigError;
+use crate::{alerts::AlertRule, audit::AuditAction, columns::{self, ColumnConfig}, errors::ConfigError, hooks::Hook, keymap::KeySpec, protection::ProtectedRule, text_input::TextInput, theme::ThemeOverrides};
+
+const CONFIG_PATH: &str = "./config.toml";
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
@@ -14,6 +16,198 @@ pub struct Config {
     pub ip: String,
     pub porta: String,
     pub request_timeout_in_secs: u64,
+    #[serde(default = "default_audit_file")]
+    pub audit_file: String,
+    /// Mensagens enviadas, recuperadas com ↑ ↓ no modal de mensagem.
+    #[serde(default = "default_message_history_file")]
+    pub message_history_file: String,
+    /// Pasta dos arquivos exportados pela tecla `x`.
+    #[serde(default = "default_export_dir")]
+    pub export_dir: String,
+    #[serde(default = "default_page_size")]
+    pub page_size: i32,
+    /// `dark`, `light`, `high-contrast`, `mono` ou o nome de um `[themes.<nome>]`.
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    /// Modo somente leitura: bloqueia desconexões e, com `read_only_messages`, também mensagens.
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub read_only_messages: bool,
+    /// Somente leitura pedido pela linha de comando (`--read-only`); não é gravado no config.toml.
+    #[serde(skip)]
+    pub forced_read_only: bool,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub highlight: HighlightConfig,
+    #[serde(default)]
+    pub delete_confirmation: DeleteConfirmationConfig,
+    #[serde(default)]
+    pub exporter: ExporterConfig,
+    #[serde(default)]
+    pub serve: ServeConfig,
+    /// Conexões que não podem ser desconectadas sem digitar `delete_confirmation.override_word`.
+    #[serde(default)]
+    pub protected: Vec<ProtectedRule>,
+    /// Regras de alerta avaliadas a cada atualização do monitor.
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    /// Ganchos disparados nos eventos de `hooks::HookEvent`.
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    /// Modelos de mensagem por nome, com os marcadores de `message::PLACEHOLDERS`.
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
+    /// Teclas de cada ação; as ações ausentes usam as teclas padrão.
+    #[serde(default)]
+    pub keys: BTreeMap<String, KeySpec>,
+    #[serde(default)]
+    pub themes: BTreeMap<String, ThemeOverrides>,
+    #[serde(default = "columns::default_columns")]
+    pub columns: Vec<ColumnConfig>,
+}
+
+impl Config {
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || self.forced_read_only
+    }
+
+    /// Se o modo somente leitura bloqueia a ação.
+    pub fn blocks(&self, action: AuditAction) -> bool {
+        self.is_read_only() && (action == AuditAction::Delete || self.read_only_messages)
+    }
+}
+
+fn default_audit_file() -> String {
+    "./audit.jsonl".to_string()
+}
+
+fn default_message_history_file() -> String {
+    "./message_history.txt".to_string()
+}
+
+fn default_export_dir() -> String {
+    "./exports".to_string()
+}
+
+fn default_page_size() -> i32 {
+    10
+}
+
+fn default_theme() -> String {
+    "dark".to_string()
+}
+
+/// Seção `[history]` do config.toml.
+///
+/// Quando habilitado, cada atualização grava a listagem completa de conexões em `dir`,
+/// um arquivo por dia, e arquivos com mais de `retention_days` dias são apagados.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    pub dir: String,
+    pub retention_days: u64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "./history".to_string(),
+            retention_days: 30,
+        }
+    }
+}
+
+/// Seção `[highlight]` do config.toml.
+///
+/// Controla por quanto tempo as mudanças entre atualizações ficam destacadas na tabela e
+/// a partir de qual aumento de memória (MB) ou de instruções por segundo uma linha é marcada.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct HighlightConfig {
+    pub fade_secs: u64,
+    pub memory_jump_mb: u64,
+    pub instructions_jump: u64,
+}
+
+impl Default for HighlightConfig {
+    fn default() -> Self {
+        Self {
+            fade_secs: 10,
+            memory_jump_mb: 100,
+            instructions_jump: 1000,
+        }
+    }
+}
+
+
+/// Seção `[delete_confirmation]` do config.toml.
+///
+/// Para desconectar mais de `type_word_above` conexões de uma vez é preciso digitar `word`
+/// no lugar de só confirmar com uma tecla. Se houver conexões protegidas entre elas, a palavra
+/// é `override_word`, qualquer que seja a quantidade.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct DeleteConfirmationConfig {
+    pub type_word_above: usize,
+    pub word: String,
+    pub override_word: String,
+}
+
+impl Default for DeleteConfirmationConfig {
+    fn default() -> Self {
+        Self {
+            type_word_above: 5,
+            word: "DESCONECTAR".to_string(),
+            override_word: "FORCAR".to_string(),
+        }
+    }
+}
+
+
+/// Seção `[exporter]` do config.toml, usada por `climonitor exporter`.
+///
+/// `listen` é o endereço do `/metrics` quando `--listen` não é informado, e `idle_minutes` os
+/// limites de inatividade contados em `climonitor_idle_connections`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct ExporterConfig {
+    pub listen: String,
+    pub idle_minutes: Vec<u64>,
+}
+
+impl Default for ExporterConfig {
+    fn default() -> Self {
+        Self {
+            listen: "0.0.0.0:9464".to_string(),
+            idle_minutes: vec![15, 30, 60],
+        }
+    }
+}
+
+
+/// Seção `[serve]` do config.toml, usada por `climonitor serve`.
+///
+/// `listen` é o endereço da API quando `--listen` não é informado. Com `api_key` preenchida,
+/// toda requisição precisa do cabeçalho `Authorization: Bearer <api_key>`; sem ela, a API só
+/// aceita escutar em um endereço local.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct ServeConfig {
+    pub listen: String,
+    pub api_key: String,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self {
+            listen: "127.0.0.1:9465".to_string(),
+            api_key: String::new(),
+        }
+    }
 }
 
 
@@ -27,17 +221,13 @@ pub struct Config {
 /// # Panics
 ///
 /// This function will panic if there is an error reading the file or parsing the TOML data.
-
 pub fn load_config() -> Result<Config, ConfigError>{
-    let path = "./config.toml";
+    let path = CONFIG_PATH;
 
     if fs::metadata(path).is_err() {
         println!("config.toml não encontrado");
         
-        let config = match create_new_config(){
-            Ok(config) => config,
-            Err(e) => return Err(e),
-        };
+        let config = create_new_config()?;
         let toml_str = match toml::to_string(&config){
             Ok(toml_str) => toml_str,
             Err(e) => return Err(ConfigError::Parsing(e.to_string())),
@@ -55,9 +245,33 @@ pub fn load_config() -> Result<Config, ConfigError>{
         Ok(config) => config,
         Err(e) => return Err(ConfigError::Parsing(e.to_string())),
     };
-    return Ok(config)
+    Ok(config)
+
 
+}
 
+
+/// Grava as colunas escolhidas no modal de colunas no config.toml.
+///
+/// Só o `[[columns]]` é trocado; o resto do arquivo, com comentários, ordem e chaves que esta
+/// versão não conhece, fica como está.
+pub fn save_columns(columns: &[ColumnConfig]) -> Result<(), ConfigError> {
+    #[derive(Serialize)]
+    struct Columns<'a> {
+        columns: &'a [ColumnConfig],
+    }
+
+    let toml_str = fs::read_to_string(CONFIG_PATH).map_err(|e| ConfigError::Parsing(e.to_string()))?;
+    let mut document: DocumentMut = toml_str.parse().map_err(|e: toml_edit::TomlError| ConfigError::Parsing(e.to_string()))?;
+    let new: DocumentMut = toml::to_string(&Columns { columns })
+        .map_err(|e| ConfigError::Parsing(e.to_string()))?
+        .parse()
+        .map_err(|e: toml_edit::TomlError| ConfigError::Parsing(e.to_string()))?;
+    document["columns"] = new["columns"].clone();
+    if let Some(first) = document["columns"].as_array_of_tables_mut().and_then(|tables| tables.get_mut(0)) {
+        first.decor_mut().set_prefix("\n");
+    }
+    fs::write(CONFIG_PATH, document.to_string()).map_err(|e| ConfigError::WriteFileError(e.to_string()))
 }
 
 
@@ -74,34 +288,24 @@ pub fn create_new_config() -> Result<Config, ConfigError>{
 
     fn read_t_line(prompt: &str) -> Result<String, ConfigError> {
         println!("{}", prompt);
-        let mut input = String::new();
-        match stdin().read_line(&mut input){
-            Ok(_) => {}
-            Err(e) => return Err(ConfigError::ReadLineError(e.to_string())),
-        };
+        let input = read_input(None, |_| true, ConfigError::ReadLineError)?;
         println!();
         Ok(input)
     }
 
     fn read_t_numbers(prompt : &str) -> Result<u64, ConfigError> {
         println!("{}", prompt);
-        let input : u64 = match read_only_numbers(){
-            Ok(input) => input,
-            Err(e) => return Err(e),
-        };
+        let input = read_input(None, |c| c.is_ascii_digit(), ConfigError::ReadNumberLineError)?;
         println!();
-        Ok(input)
+        input.parse().map_err(|e : std::num::ParseIntError| ConfigError::ReadNumberLineError(e.to_string()))
     }
 
 
     let login = read_t_line("Digite o login: ")?;
 
     println!("Digite a senha: ");
-    let password = match read_with_mask('*') {
-        Ok(password) => password,
-        Err(e) => return Err(e),  
-    };
-    println!("");
+    let password = read_input(Some('*'), |_| true, ConfigError::ReadWithMaskError)?;
+    println!();
 
 
     let enviorment = read_t_line("Digite o ambiente: ")?;
@@ -116,105 +320,83 @@ pub fn create_new_config() -> Result<Config, ConfigError>{
         login: login.trim().to_string(),
         password: password.trim().to_string(),
         enviorment: enviorment.trim().to_string(),
-        refresh_interval_in_secs: refresh_interval_in_secs,
+        refresh_interval_in_secs,
         ip: ip.trim().to_string(),
         porta: porta.trim().to_string(),
-        request_timeout_in_secs: 15
+        request_timeout_in_secs: 15,
+        audit_file: default_audit_file(),
+        message_history_file: default_message_history_file(),
+        export_dir: default_export_dir(),
+        page_size: default_page_size(),
+        theme: default_theme(),
+        read_only: false,
+        read_only_messages: false,
+        forced_read_only: false,
+        history: HistoryConfig::default(),
+        highlight: HighlightConfig::default(),
+        delete_confirmation: DeleteConfirmationConfig::default(),
+        exporter: ExporterConfig::default(),
+        serve: ServeConfig::default(),
+        protected: Vec::new(),
+        alerts: Vec::new(),
+        hooks: Vec::new(),
+        templates: BTreeMap::new(),
+        keys: BTreeMap::new(),
+        themes: BTreeMap::new(),
+        columns: columns::default_columns(),
     };
 
     Ok(config)
 }
 
-/// Reads a string from stdin, echoing each character as `mask` instead of the actual character.
+/// Reads a line from the terminal with the same text input used by the monitor.
 ///
-/// This is useful for reading passwords from the user without echoing the password to the console.
+/// The cursor moves with the arrows and Home/End, Ctrl-W and Ctrl-U erase the word or everything
+/// before the cursor, and pasted text is inserted at the cursor. Only the characters accepted by
+/// `accept` are kept. With a `mask`, each character is echoed as the mask instead, which is
+/// useful for reading passwords.
 ///
-/// # Return
+/// # Arguments
 ///
-/// A `String` containing the input string.
-fn read_with_mask(mask : char) -> Result<String, ConfigError> {
-    let mut stdout = stdout();
-    let mut password = String::new();
-
-    enable_raw_mode().map_err(|e| ConfigError::ReadWithMaskError(e.to_string()))?;
-    stdout.execute(cursor::MoveToColumn(0)).map_err(|e| ConfigError::ReadWithMaskError(e.to_string()))?;
-    stdout.flush().map_err(|e| ConfigError::ReadWithMaskError(e.to_string()))?;
-
-    loop {
-        if let Event::Key(event) = read().map_err(|e| ConfigError::ReadWithMaskError(e.to_string()))? {
-            match event.code {
-                KeyCode::Enter => {
-                    println!();
-                    break;
-                }
-                KeyCode::Char(c) => {
-                    password.push(c);
-                    print!("{}", mask);
-                    stdout.flush().map_err(|e| ConfigError::ReadWithMaskError(e.to_string()))?;
-                }
-                KeyCode::Backspace => {
-                    if password.pop().is_some() {
-                        print!("\x08 \x08"); // backspace visual
-                        stdout.flush().map_err(|e| ConfigError::ReadWithMaskError(e.to_string()))?;
-                    }
-                }
-                _ => {}
-            }
-        }
-    }
-
-    disable_raw_mode().map_err(|e| ConfigError::ReadWithMaskError(e.to_string()))?;
-    Ok(password)
-}
-
-
-/// Reads a number from the user by processing key events.
-///
-/// This function enables raw mode and starts reading key events. It will only accept numeric
-/// characters and backspace. When Enter is pressed, it will parse the input string as a number
-/// and return it. If there is an error parsing the number, or if there is an error reading the
-/// key event, it will return an error.
+/// * `mask` - The character echoed in place of the typed ones, if any.
+/// * `accept` - Which characters may be typed or pasted.
+/// * `error` - Builds the error returned when the terminal fails.
 ///
 /// # Return
 ///
-/// A `Result` containing the input number, or a `ConfigError` if there is an error.
-fn read_only_numbers() -> Result<u64, ConfigError> {
+/// A `String` containing the input line.
+fn read_input(mask: Option<char>, accept: fn(char) -> bool, error: fn(String) -> ConfigError) -> Result<String, ConfigError> {
     let mut stdout = stdout();
-    let mut numbers = String::new();
+    let mut input = TextInput::default();
+    let fail = |e: std::io::Error| error(e.to_string());
 
-    enable_raw_mode().map_err(|e| ConfigError::ReadNumberLineError(e.to_string()))?;
-    stdout.execute(cursor::MoveToColumn(0)).map_err(|e| ConfigError::ReadNumberLineError(e.to_string()))?;
-    stdout.flush().map_err(|e| ConfigError::ReadNumberLineError(e.to_string()))?;
+    enable_raw_mode().map_err(fail)?;
+    stdout.execute(EnableBracketedPaste).map_err(fail)?;
 
     loop {
-        if let Event::Key(event) = read().map_err(|e| ConfigError::ReadNumberLineError(e.to_string()))? {
-            match event.code {
-                KeyCode::Enter => {
-                    println!();
-                    break;
-                }
-                KeyCode::Backspace => {
-                    if numbers.pop().is_some() {
-                        print!("\x08 \x08"); // backspace visual
-                        stdout.flush().map_err(|e| ConfigError::ReadNumberLineError(e.to_string()))?;
-                    }
-                }
-                KeyCode::Char(c) => {
-                    if c.is_numeric() {
-                        numbers.push(c);
-                        print!("{}", c);
-                        stdout.flush().map_err(|e| ConfigError::ReadNumberLineError(e.to_string()))?;
-                    }
+        let (shown, column) = input.display(mask);
+        stdout.execute(cursor::MoveToColumn(0)).map_err(fail)?;
+        stdout.execute(Clear(ClearType::CurrentLine)).map_err(fail)?;
+        print!("{}", shown);
+        stdout.execute(cursor::MoveToColumn(column)).map_err(fail)?;
+        stdout.flush().map_err(fail)?;
+
+        match read().map_err(fail)? {
+            Event::Key(key) => match key.code {
+                KeyCode::Enter => break,
+                KeyCode::Char(c) if !accept(c) => {}
+                _ => {
+                    input.handle(&key);
                 }
-                _ => {}
-            }
+            },
+            Event::Paste(text) => input.insert_str(&text.chars().filter(|c| accept(*c)).collect::<String>()),
+            _ => {}
         }
     }
 
-    disable_raw_mode().map_err(|e| ConfigError::ReadNumberLineError(e.to_string()))?;
-
-    let numbers_result : u64 = numbers.parse().map_err(|e : std::num::ParseIntError| ConfigError::ReadNumberLineError(e.to_string()))?;
-
-    Ok(numbers_result)
+    stdout.execute(DisableBracketedPaste).map_err(fail)?;
+    disable_raw_mode().map_err(fail)?;
+    println!();
+    Ok(input.text().to_string())
 }
 
@@ -0,0 +1,151 @@
+use std::collections::VecDeque;
+
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Sparkline, Wrap},
+    Frame,
+};
+
+use crate::{api_service::Entry, keymap::{Action, KeyMap}, theme::Theme, trends::format_bytes};
+
+/// Quantidade máxima de amostras guardadas para a conexão acompanhada.
+const CAPACITY: usize = 240;
+
+
+/// Conexão acompanhada na tela de detalhes.
+///
+/// A cada atualização da listagem completa a conexão é procurada pelo `Entry.id` e os valores
+/// de memória e instruções/s entram no histórico exibido nos gráficos.
+pub struct Detail {
+    pub entry: Entry,
+    /// A conexão não apareceu na última atualização; os dados são os últimos conhecidos.
+    pub gone: bool,
+    memory: VecDeque<u64>,
+    instructions_ps: VecDeque<u64>,
+}
+
+impl Detail {
+    pub fn new(entry: &Entry) -> Self {
+        let mut detail = Self {
+            entry: entry.clone(),
+            gone: false,
+            memory: VecDeque::new(),
+            instructions_ps: VecDeque::new(),
+        };
+        detail.push(entry);
+        detail
+    }
+
+    pub fn update(&mut self, entries: &[Entry]) {
+        match entries.iter().find(|e| e.id == self.entry.id) {
+            Some(entry) => {
+                self.entry = entry.clone();
+                self.gone = false;
+                self.push(entry);
+            }
+            None => self.gone = true,
+        }
+    }
+
+    fn push(&mut self, entry: &Entry) {
+        if self.memory.len() == CAPACITY {
+            self.memory.pop_front();
+            self.instructions_ps.pop_front();
+        }
+        self.memory.push_back(entry.memory.max(0) as u64);
+        self.instructions_ps.push_back(entry.instructions_ps.max(0) as u64);
+    }
+}
+
+
+fn tail(series: &VecDeque<u64>, width: u16) -> Vec<u64> {
+    let skip = series.len().saturating_sub(width as usize);
+    series.iter().skip(skip).copied().collect()
+}
+
+
+/// Renderiza a tela de detalhes ocupando o terminal inteiro.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `detail` - conexão acompanhada.
+/// * `keymap` - teclas ativas, para o rodapé de ações.
+pub fn draw_detail(f: &mut Frame, theme: &Theme, detail: &Detail, keymap: &KeyMap) {
+    let area = f.area();
+    f.render_widget(Clear, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(12), Constraint::Length(6), Constraint::Length(6), Constraint::Length(3)])
+        .split(area);
+
+    let entry = &detail.entry;
+    let title = if detail.gone {
+        format!("{} - {} (desconectada)", entry.user_name.trim(), entry.function.trim())
+    } else {
+        format!("{} - {}", entry.user_name.trim(), entry.function.trim())
+    };
+    let title_style = if detail.gone { theme.error_text() } else { theme.accent() };
+
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let field = |label: &str, value: String| {
+        Line::from(vec![Span::styled(format!("{:<18}", label), theme.muted()), Span::styled(value, bold)])
+    };
+    let lines = vec![
+        field("Usuário", entry.user_name.trim().to_string()),
+        field("Computador", entry.machine_name.trim().to_string()),
+        field("Programa", entry.function.trim().to_string()),
+        field("Ambiente", entry.environment.trim().to_string()),
+        field("Tipo de conexão", entry.thread_type.trim().to_string()),
+        field("Servidor", entry.server.trim().to_string()),
+        field("Thread ID", entry.thread_id.to_string()),
+        field("SID", entry.s_id.trim().to_string()),
+        field("Ctree", entry.id_ctree.to_string()),
+        field("Data/Hora", entry.date_time.trim().to_string()),
+        field("Tempo de conexão", entry.time_up.trim().to_string()),
+        field("Tempo inativo", entry.inactive_time.trim().to_string()),
+        field("Memória", format_bytes(entry.memory.max(0) as u64)),
+        field("Instruções", entry.instructions.to_string()),
+        field("Instruções P/s", entry.instructions_ps.to_string()),
+        field("Comentários", entry.comments.trim().to_string()),
+    ];
+    f.render_widget(
+        Paragraph::new(Text::from(lines))
+            .block(Block::default().title(Span::styled(title, title_style)).borders(Borders::ALL).border_style(theme.border()))
+            .wrap(Wrap { trim: false }),
+        chunks[0],
+    );
+
+    let charts = [
+        (format!("memória: {}", format_bytes(entry.memory.max(0) as u64)), theme.chart_memory, &detail.memory, chunks[1]),
+        (format!("instruções/s: {}", entry.instructions_ps), theme.chart_instructions, &detail.instructions_ps, chunks[2]),
+    ];
+    for (title, color, series, area) in charts {
+        let data = tail(series, area.width.saturating_sub(2));
+        f.render_widget(
+            Sparkline::default()
+                .block(Block::default().title(title).borders(Borders::ALL).border_style(theme.border()))
+                .data(&data)
+                .style(Style::default().fg(theme.chart(color))),
+            area,
+        );
+    }
+
+    let footer = format!(
+        "Desconectar <{}>  Mensagem <{}>  Atualizar <{}>  Fechar <esc/q>",
+        keymap.keys_label(Action::Delete),
+        keymap.keys_label(Action::Message),
+        keymap.keys_label(Action::Refresh),
+    );
+    f.render_widget(
+        Paragraph::new(footer)
+            .style(bold)
+            .block(Block::default().title("comandos").borders(Borders::ALL).border_style(theme.footer_border())),
+        chunks[3],
+    );
+}
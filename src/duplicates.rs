@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::api_service::Entry;
+
+
+/// Por que uma conexão foi marcada como sessão duplicada.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Duplicate {
+    /// O usuário tem mais de uma sessão do mesmo programa.
+    SameProgram,
+    /// O usuário está conectado de mais de um computador.
+    ManyMachines,
+}
+
+impl Duplicate {
+    /// Marcador exibido antes da primeira coluna.
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Duplicate::SameProgram => "◆",
+            Duplicate::ManyMachines => "◇",
+        }
+    }
+}
+
+
+/// Sessões duplicadas encontradas na última listagem completa.
+#[derive(Default)]
+pub struct Duplicates {
+    kinds: HashMap<String, Duplicate>,
+    /// Sessões mais antigas de cada usuário e programa repetidos; a mais nova de cada grupo fica de fora.
+    older: Vec<String>,
+}
+
+impl Duplicates {
+    /// Procura, na listagem completa, usuários com o mesmo programa aberto mais de uma vez e
+    /// usuários conectados de mais de um computador.
+    ///
+    /// A sessão mais nova de cada usuário e programa é a de menor tempo de conexão. Conexões sem
+    /// usuário (threads do servidor) não são agrupadas, e sessões com tempo de conexão ilegível
+    /// são marcadas mas nunca entram em `older`, já que não dá para saber se são as mais antigas.
+    pub fn find(entries: &[Entry]) -> Self {
+        let mut by_program: HashMap<(String, String), Vec<&Entry>> = HashMap::new();
+        let mut machines: HashMap<String, HashSet<String>> = HashMap::new();
+        for entry in entries {
+            let user = entry.user_name.trim().to_lowercase();
+            if user.is_empty() {
+                continue;
+            }
+            by_program.entry((user.clone(), entry.function.trim().to_lowercase())).or_default().push(entry);
+            machines.entry(user).or_default().insert(entry.machine_name.trim().to_lowercase());
+        }
+
+        let mut kinds = HashMap::new();
+        for entry in entries {
+            if machines.get(&entry.user_name.trim().to_lowercase()).is_some_and(|m| m.len() > 1) {
+                kinds.insert(entry.id.clone(), Duplicate::ManyMachines);
+            }
+        }
+
+        let mut older = Vec::new();
+        for sessions in by_program.into_values().filter(|s| s.len() > 1) {
+            for entry in &sessions {
+                kinds.insert(entry.id.clone(), Duplicate::SameProgram);
+            }
+            let mut timed: Vec<(u64, &Entry)> = sessions.iter().filter_map(|e| Some((e.time_up_secs()?, *e))).collect();
+            timed.sort_by_key(|(secs, _)| *secs);
+            older.extend(timed.iter().skip(1).map(|(_, e)| e.id.clone()));
+        }
+
+        Self { kinds, older }
+    }
+
+    pub fn kind(&self, id: &str) -> Option<Duplicate> {
+        self.kinds.get(id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+
+    /// IDs das sessões a desconectar para ficar só com a mais nova de cada usuário e programa.
+    pub fn older(&self) -> &[String] {
+        &self.older
+    }
+
+    /// Conexões duplicadas da listagem, agrupadas por usuário.
+    pub fn filter(&self, entries: &[Entry]) -> Vec<Entry> {
+        let mut duplicates: Vec<Entry> = entries.iter().filter(|e| self.kinds.contains_key(&e.id)).cloned().collect();
+        duplicates.sort_by(|a, b| {
+            a.user_name.trim().cmp(b.user_name.trim())
+                .then_with(|| a.function.trim().cmp(b.function.trim()))
+                .then_with(|| a.time_up_secs().cmp(&b.time_up_secs()))
+        });
+        duplicates
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, user_name: &str, function: &str, machine_name: &str, time_up: &str) -> Entry {
+        Entry {
+            id: id.to_string(),
+            user_name: user_name.to_string(),
+            function: function.to_string(),
+            machine_name: machine_name.to_string(),
+            time_up: time_up.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn sorted(ids: &[String]) -> Vec<&str> {
+        let mut ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn keeps_the_newest_session_of_each_user_and_program() {
+        let entries = vec![
+            entry("1", "ANA", "MATA010", "PC01", "03:00:00"),
+            entry("2", "ana ", "mata010", "PC01", "00:10:00"),
+            entry("3", "ANA", "MATA010", "PC01", "01:00:00"),
+            entry("4", "ANA", "FINA050", "PC01", "05:00:00"),
+            entry("5", "JOAO", "MATA010", "PC02", "05:00:00"),
+        ];
+        let duplicates = Duplicates::find(&entries);
+        assert_eq!(sorted(duplicates.older()), ["1", "3"]);
+        assert!(duplicates.kind("2") == Some(Duplicate::SameProgram));
+        assert!(duplicates.kind("4").is_none());
+        assert!(duplicates.kind("5").is_none());
+        assert_eq!(duplicates.len(), 3);
+    }
+
+    #[test]
+    fn marks_users_on_many_machines() {
+        let entries = vec![
+            entry("1", "ANA", "MATA010", "PC01", "01:00:00"),
+            entry("2", "ANA", "FINA050", "PC02", "01:00:00"),
+        ];
+        let duplicates = Duplicates::find(&entries);
+        assert!(duplicates.kind("1") == Some(Duplicate::ManyMachines));
+        assert!(duplicates.kind("2") == Some(Duplicate::ManyMachines));
+        assert!(duplicates.older().is_empty());
+    }
+
+    #[test]
+    fn ignores_connections_without_user() {
+        let entries = vec![
+            entry("1", "", "RPC", "SRV01", "03:00:00"),
+            entry("2", "  ", "RPC", "SRV02", "00:10:00"),
+            entry("3", "", "RPC", "SRV01", "01:00:00"),
+        ];
+        let duplicates = Duplicates::find(&entries);
+        assert_eq!(duplicates.len(), 0);
+        assert!(duplicates.older().is_empty());
+    }
+
+    #[test]
+    fn never_kills_sessions_with_unreadable_time() {
+        let entries = vec![
+            entry("1", "ANA", "MATA010", "PC01", "03:00:00"),
+            entry("2", "ANA", "MATA010", "PC01", ""),
+            entry("3", "ANA", "MATA010", "PC01", "00:10:00"),
+            entry("4", "ANA", "MATA010", "PC01", "??"),
+        ];
+        let duplicates = Duplicates::find(&entries);
+        assert_eq!(sorted(duplicates.older()), ["1"]);
+        assert_eq!(duplicates.len(), 4);
+
+        let unreadable = vec![entry("1", "ANA", "MATA010", "PC01", ""), entry("2", "ANA", "MATA010", "PC01", "")];
+        assert!(Duplicates::find(&unreadable).older().is_empty());
+    }
+}
@@ -42,10 +42,13 @@ impl std::fmt::Display for APIError {
 }
 
 
+#[allow(clippy::enum_variant_names)]
 pub enum TerminalError{
     AuthError(String),
     ConfigError(String),
     DrawError(String),
+    ServeError(String),
+    HookError(String),
 }
 
 impl std::fmt::Display for TerminalError {
@@ -54,6 +57,8 @@ impl std::fmt::Display for TerminalError {
             TerminalError::AuthError(s) => write!(f, "Erro ao tentar autenticar {}", s),
             TerminalError::ConfigError(s) => write!(f, "Erro ao tentar carregar config {}", s),
             TerminalError::DrawError(s) => write!(f, "Erro ao tentar desenhar terminal {}", s),
+            TerminalError::ServeError(s) => write!(f, "Erro no servidor HTTP {}", s),
+            TerminalError::HookError(s) => write!(f, "Erro nos ganchos {}", s),
         }
     }
 }
@@ -82,4 +87,146 @@ impl From<reqwest::Error> for TerminalError {
     fn from(e: reqwest::Error) -> Self {
         TerminalError::AuthError(e.to_string())
     }
-}
\ No newline at end of file
+}
+
+pub enum AuditError{
+    OpenFileError(String),
+    WriteFileError(String),
+    Parsing(String),
+}
+
+impl std::fmt::Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AuditError::OpenFileError(s) => write!(f, "Erro ao tentar abrir log de auditoria {}", s),
+            AuditError::WriteFileError(s) => write!(f, "Erro ao tentar gravar log de auditoria {}", s),
+            AuditError::Parsing(s) => write!(f, "Erro ao tentar parsear log de auditoria {}", s),
+        }
+    }
+}
+
+impl From<AuditError> for TerminalError {
+    fn from(e: AuditError) -> Self {
+        TerminalError::ConfigError(e.to_string())
+    }
+}
+
+
+pub enum HistoryError{
+    CreateDirError(String),
+    OpenFileError(String),
+    WriteFileError(String),
+    Parsing(String),
+}
+
+impl std::fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HistoryError::CreateDirError(s) => write!(f, "Erro ao tentar criar diretório de histórico {}", s),
+            HistoryError::OpenFileError(s) => write!(f, "Erro ao tentar abrir histórico {}", s),
+            HistoryError::WriteFileError(s) => write!(f, "Erro ao tentar gravar histórico {}", s),
+            HistoryError::Parsing(s) => write!(f, "Erro ao tentar parsear histórico {}", s),
+        }
+    }
+}
+
+impl From<HistoryError> for TerminalError {
+    fn from(e: HistoryError) -> Self {
+        TerminalError::ConfigError(e.to_string())
+    }
+}
+
+
+pub enum ArgsError{
+    UnknownArgument(String),
+    MissingValue(String),
+    InvalidValue(String),
+}
+
+impl std::fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ArgsError::UnknownArgument(s) => write!(f, "Argumento desconhecido {}", s),
+            ArgsError::MissingValue(s) => write!(f, "Faltando valor para o argumento {}", s),
+            ArgsError::InvalidValue(s) => write!(f, "Valor inválido {}", s),
+        }
+    }
+}
+
+
+pub enum HttpError{
+    Bind(String),
+    BadRequest(String),
+    TooLarge(usize),
+    LineTooLong(usize),
+}
+
+impl HttpError {
+    /// Status HTTP respondido ao cliente quando a requisição não pôde ser lida.
+    pub fn status(&self) -> u16 {
+        match self {
+            HttpError::Bind(_) => 500,
+            HttpError::BadRequest(_) => 400,
+            HttpError::TooLarge(_) => 413,
+            HttpError::LineTooLong(_) => 431,
+        }
+    }
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HttpError::Bind(s) => write!(f, "Erro ao tentar escutar em {}", s),
+            HttpError::BadRequest(s) => write!(f, "Requisição inválida {}", s),
+            HttpError::TooLarge(n) => write!(f, "Corpo da requisição grande demais ({} bytes)", n),
+            HttpError::LineTooLong(n) => write!(f, "Linha da requisição ou cabeçalho maior que {} bytes", n),
+        }
+    }
+}
+
+impl From<HttpError> for TerminalError {
+    fn from(e: HttpError) -> Self {
+        TerminalError::ServeError(e.to_string())
+    }
+}
+
+
+pub enum HookError{
+    Config(String),
+    Delivery(String, String),
+    Failed(usize),
+}
+
+impl std::fmt::Display for HookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HookError::Config(s) => write!(f, "Gancho sem url nem command {}", s),
+            HookError::Delivery(hook, s) => write!(f, "Erro ao tentar disparar gancho {}: {}", hook, s),
+            HookError::Failed(n) => write!(f, "{} gancho(s) falharam", n),
+        }
+    }
+}
+
+impl From<HookError> for TerminalError {
+    fn from(e: HookError) -> Self {
+        TerminalError::HookError(e.to_string())
+    }
+}
+
+
+#[allow(clippy::enum_variant_names)]
+pub enum ExportError{
+    CreateDirError(String),
+    WriteFileError(String),
+    SerializeError(String),
+}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExportError::CreateDirError(s) => write!(f, "Erro ao tentar criar diretório de exportação {}", s),
+            ExportError::WriteFileError(s) => write!(f, "Erro ao tentar gravar arquivo exportado {}", s),
+            ExportError::SerializeError(s) => write!(f, "Erro ao tentar gerar o JSON exportado {}", s),
+        }
+    }
+}
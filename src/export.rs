@@ -0,0 +1,289 @@
+use std::{collections::BTreeMap, fmt::Write as _, fs::{self, File, OpenOptions}, io::{self, Write}, path::{Path, PathBuf}};
+
+use chrono::{DateTime, Local};
+use serde_json::json;
+
+use crate::{api_service::{Connection, Entry}, columns::Field, config::Config, errors::ExportError};
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Csv, ExportFormat::Json, ExportFormat::Html];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Html => "HTML",
+        }
+    }
+
+    /// Tecla do formato no modal de exportação.
+    pub fn key(&self) -> char {
+        match self {
+            ExportFormat::Csv => 'c',
+            ExportFormat::Json => 'j',
+            ExportFormat::Html => 'h',
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+
+/// Estado do modal de exportação (`x`).
+#[derive(Default)]
+pub struct Export {
+    /// Exporta a listagem completa no lugar da visão atual da tabela.
+    pub full: bool,
+    /// Último arquivo gravado, mostrado no modal.
+    pub last: Option<PathBuf>,
+}
+
+
+/// Cabeçalho dos arquivos exportados: de onde, quando, o que e os totais.
+struct Summary<'a> {
+    server: String,
+    environment: &'a str,
+    time: DateTime<Local>,
+    scope: &'a str,
+    by_environment: BTreeMap<String, usize>,
+    by_user: BTreeMap<String, usize>,
+}
+
+impl<'a> Summary<'a> {
+    fn new(config: &'a Config, scope: &'a str, entries: &[Entry]) -> Self {
+        let mut by_environment = BTreeMap::new();
+        let mut by_user = BTreeMap::new();
+        for entry in entries {
+            *by_environment.entry(entry.environment.trim().to_string()).or_default() += 1;
+            *by_user.entry(entry.user_name.trim().to_string()).or_default() += 1;
+        }
+        Self {
+            server: format!("{}:{}", config.ip, config.porta),
+            environment: &config.enviorment,
+            time: Local::now(),
+            scope,
+            by_environment,
+            by_user,
+        }
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "CLIMonitor - {} ({}) em {}",
+            self.server,
+            self.environment,
+            self.time.format("%d/%m/%Y %H:%M:%S")
+        )
+    }
+}
+
+
+/// Writes `entries` to a new `climonitor-AAAAMMDD-HHMMSS` file in `config.export_dir`, adding a
+/// `-2`, `-3`, ... suffix when an export from the same second already exists.
+///
+/// CSV and HTML carry the given columns, formatted as in the table; JSON carries every field, as
+/// returned by `climonitor serve`. Every format starts with a summary: server, environment, time,
+/// `scope` and the connection totals per environment and per user.
+///
+/// # Errors
+///
+/// Returns an `ExportError` if the directory can't be created, the JSON can't be generated or the
+/// file can't be written. No file is created when the content can't be generated.
+pub fn export(config: &Config, entries: &[Entry], columns: &[Field], scope: &str, format: ExportFormat) -> Result<PathBuf, ExportError> {
+    fs::create_dir_all(&config.export_dir).map_err(|e| ExportError::CreateDirError(e.to_string()))?;
+
+    let summary = Summary::new(config, scope, entries);
+    let content = match format {
+        ExportFormat::Csv => csv(&summary, entries, columns),
+        ExportFormat::Json => {
+            let connections: Vec<Connection> = entries.iter().map(|e| Connection::new(e, config)).collect();
+            serde_json::to_string_pretty(&json!({
+                "server": summary.server,
+                "environment": summary.environment,
+                "generated_at": summary.time.to_rfc3339(),
+                "scope": summary.scope,
+                "total": entries.len(),
+                "by_environment": summary.by_environment,
+                "by_user": summary.by_user,
+                "connections": connections,
+            }))
+            .map_err(|e| ExportError::SerializeError(e.to_string()))?
+        }
+        ExportFormat::Html => html(&summary, entries, columns),
+    };
+
+    let stem = format!("climonitor-{}", summary.time.format("%Y%m%d-%H%M%S"));
+    let (path, mut file) = create_file(Path::new(&config.export_dir), &stem, format.extension())
+        .map_err(|e| ExportError::WriteFileError(format!("{}: {}", config.export_dir, e)))?;
+    file.write_all(content.as_bytes()).map_err(|e| ExportError::WriteFileError(format!("{}: {}", path.display(), e)))?;
+    Ok(path)
+}
+
+
+/// Creates `stem.extension` in `dir` without overwriting an existing file, trying `stem-2`,
+/// `stem-3`, ... while the name is taken.
+fn create_file(dir: &Path, stem: &str, extension: &str) -> io::Result<(PathBuf, File)> {
+    let mut n = 1;
+    loop {
+        let name = match n {
+            1 => format!("{}.{}", stem, extension),
+            n => format!("{}-{}.{}", stem, n, extension),
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+
+/// Totais como `P12 15, P12_TST 11`.
+fn totals(counts: &BTreeMap<String, usize>) -> String {
+    counts.iter().map(|(name, n)| format!("{} {}", name, n)).collect::<Vec<_>>().join(", ")
+}
+
+
+/// CSV with the summary in `#` lines before the header row.
+fn csv(summary: &Summary, entries: &[Entry], columns: &[Field]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}", summary.title());
+    let _ = writeln!(out, "# {}: {} conexões", summary.scope, entries.len());
+    let _ = writeln!(out, "# por ambiente: {}", totals(&summary.by_environment));
+    let _ = writeln!(out, "# por usuário: {}", totals(&summary.by_user));
+
+    let row = |values: Vec<String>| values.iter().map(|v| csv_field(v)).collect::<Vec<_>>().join(",");
+    let _ = writeln!(out, "{}", row(columns.iter().map(|c| c.label().to_string()).collect()));
+    for entry in entries {
+        let _ = writeln!(out, "{}", row(columns.iter().map(|c| c.value(entry)).collect()));
+    }
+    out
+}
+
+/// Quotes a CSV field when needed. Values starting with `=`, `+`, `-`, `@`, a tab or a carriage
+/// return get a leading `'`, so spreadsheets don't run them as formulas.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+
+/// Self-contained HTML page: inline style, no external resources.
+fn html(summary: &Summary, entries: &[Entry], columns: &[Field]) -> String {
+    let mut out = String::new();
+    let title = escape(&summary.title());
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"pt-BR\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: sans-serif; margin: 2em; color: #222; }}\n\
+         table {{ border-collapse: collapse; margin-bottom: 1.5em; }}\n\
+         th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; }}\n\
+         th {{ background: #eee; }}\n\
+         tr:nth-child(even) td {{ background: #f8f8f8; }}\n\
+         .totals {{ display: flex; gap: 2em; }}\n\
+         </style>\n</head>\n<body>\n<h1>{}</h1>\n<p>{}: {} conexões</p>\n",
+        title,
+        title,
+        escape(summary.scope),
+        entries.len()
+    );
+
+    out.push_str("<div class=\"totals\">\n");
+    for (label, counts) in [("Ambiente", &summary.by_environment), ("Usuário", &summary.by_user)] {
+        let _ = write!(out, "<table>\n<tr><th>{}</th><th>Conexões</th></tr>\n", label);
+        for (name, n) in counts {
+            let _ = writeln!(out, "<tr><td>{}</td><td>{}</td></tr>", escape(name), n);
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</div>\n<table>\n<tr>");
+    for column in columns {
+        let _ = write!(out, "<th>{}</th>", escape(column.label()));
+    }
+    out.push_str("</tr>\n");
+    for entry in entries {
+        out.push_str("<tr>");
+        for column in columns {
+            let _ = write!(out, "<td>{}</td>", escape(&column.value(entry)));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_field_quotes_separators_and_quotes() {
+        assert_eq!(csv_field("ANA"), "ANA");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("diz \"oi\""), "\"diz \"\"oi\"\"\"");
+        assert_eq!(csv_field("duas\nlinhas"), "\"duas\nlinhas\"");
+    }
+
+    #[test]
+    fn csv_field_neutralizes_formulas() {
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-1"), "'-1");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("A=1"), "A=1");
+        assert_eq!(csv_field("\t=1+1"), "'\t=1+1");
+        assert_eq!(csv_field("\r=1+1"), "\"'\r=1+1\"");
+    }
+
+    #[test]
+    fn escape_html() {
+        assert_eq!(escape("<b>\"A&B\"</b>"), "&lt;b&gt;&quot;A&amp;B&quot;&lt;/b&gt;");
+    }
+
+    #[test]
+    fn create_file_never_overwrites() {
+        let dir = std::env::temp_dir().join(format!("climonitor-export-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let (first, _) = create_file(&dir, "climonitor-20250101-120000", "csv").unwrap();
+        let (second, _) = create_file(&dir, "climonitor-20250101-120000", "csv").unwrap();
+        let (third, _) = create_file(&dir, "climonitor-20250101-120000", "csv").unwrap();
+        assert_eq!(first.file_name().unwrap(), "climonitor-20250101-120000.csv");
+        assert_eq!(second.file_name().unwrap(), "climonitor-20250101-120000-2.csv");
+        assert_eq!(third.file_name().unwrap(), "climonitor-20250101-120000-3.csv");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+use std::{collections::BTreeMap, fmt::Write, sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+use chrono::Utc;
+use reqwest::Client;
+
+use crate::{api_service::{self, Entry}, config::Config, errors::TerminalError, hooks::{self, Watch}, http::{self, Request, Response}};
+
+/// Métrica, rótulo e campo de cada contagem de conexões.
+type Breakdown = (&'static str, &'static str, fn(&Entry) -> &str);
+
+
+/// Última listagem coletada e os erros de coleta, compartilhados entre a coleta e o `/metrics`.
+#[derive(Default)]
+struct State {
+    entries: Vec<Entry>,
+    /// A última coleta funcionou.
+    up: bool,
+    /// Instante da última coleta que funcionou, em segundos desde a época Unix.
+    last_success: Option<i64>,
+    auth_errors: u64,
+    listing_errors: u64,
+}
+
+
+/// Runs `climonitor exporter`: keeps an authenticated session, fetches the full listing every
+/// `refresh_interval_in_secs` and serves it as Prometheus metrics on `/metrics`.
+///
+/// `listen` overrides `exporter.listen` from the config.
+///
+/// # Errors
+///
+/// Returns a `TerminalError` if the HTTP client can't be built or the address can't be listened on.
+/// Failures talking to Protheus are not errors: they set `climonitor_up` to 0 and are counted in
+/// `climonitor_scrape_errors_total`.
+pub async fn run(config: Config, listen: Option<String>) -> Result<(), TerminalError> {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(config.request_timeout_in_secs))
+        .build()?;
+    let listen = listen.unwrap_or_else(|| config.exporter.listen.clone());
+    let idle_minutes = Arc::new(config.exporter.idle_minutes.clone());
+    let state = Arc::new(Mutex::new(State::default()));
+
+    tokio::spawn(poll(config, client, state.clone()));
+
+    println!("Exportando métricas em http://{}/metrics", listen);
+    http::serve(&listen, move |request| {
+        let state = state.clone();
+        let idle_minutes = idle_minutes.clone();
+        async move { respond(&request, &state, &idle_minutes) }
+    })
+    .await?;
+    Ok(())
+}
+
+
+/// Fetches the full listing forever, authenticating again when the token expires or a fetch fails.
+///
+/// Each fetch also feeds the `[[hooks]]` events, so the exporter can raise them without a terminal open.
+async fn poll(config: Config, client: Client, state: Arc<Mutex<State>>) {
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.refresh_interval_in_secs.max(1)));
+    let mut token: Option<(String, Instant)> = None;
+    let mut watch = Watch::default();
+
+    loop {
+        ticker.tick().await;
+        for failure in hooks::take_failures() {
+            eprintln!("{}", failure);
+        }
+
+        let current = match token.take() {
+            Some((token, since)) if since.elapsed() < api_service::TOKEN_TTL => (token, since),
+            _ => match api_service::get_token(&config, &client).await {
+                Ok(token) => (token, Instant::now()),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    watch.failure(&config, &e.to_string());
+                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    state.auth_errors += 1;
+                    state.up = false;
+                    continue;
+                }
+            },
+        };
+
+        let result = api_service::get_all_entries(&config, &current.0, &client).await;
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(entries) => {
+                watch.listing(&config, &entries);
+                state.entries = entries;
+                state.up = true;
+                state.last_success = Some(Utc::now().timestamp());
+                token = Some(current);
+            }
+            // O token pode ter expirado no servidor; a próxima coleta autentica de novo.
+            Err(e) => {
+                eprintln!("{}", e);
+                watch.failure(&config, &e.to_string());
+                state.listing_errors += 1;
+                state.up = false;
+            }
+        }
+    }
+}
+
+
+fn respond(request: &Request, state: &Mutex<State>, idle_minutes: &[u64]) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/metrics") => {
+            let state = state.lock().unwrap_or_else(|e| e.into_inner());
+            Response::new(200, "text/plain; version=0.0.4; charset=utf-8", render(&state, idle_minutes))
+        }
+        ("GET", "/") => Response::text(200, "CLIMonitor exporter - métricas em /metrics\n"),
+        (_, "/metrics" | "/") => Response::text(405, "Use GET\n"),
+        _ => Response::text(404, "Não encontrado\n"),
+    }
+}
+
+
+/// Writes the metrics in the Prometheus text exposition format.
+///
+/// The listing metrics are only written after the first successful fetch, so a scrape right after
+/// startup doesn't report zero connections.
+fn render(state: &State, idle_minutes: &[u64]) -> String {
+    let mut out = String::new();
+
+    family(&mut out, "climonitor_up", "gauge", "Whether the last fetch of the Protheus listing succeeded.");
+    sample(&mut out, "climonitor_up", &[], state.up as u64);
+
+    family(&mut out, "climonitor_scrape_errors_total", "counter", "Failed fetches from Protheus, by stage.");
+    sample(&mut out, "climonitor_scrape_errors_total", &[("stage", "auth")], state.auth_errors);
+    sample(&mut out, "climonitor_scrape_errors_total", &[("stage", "listing")], state.listing_errors);
+
+    let Some(last_success) = state.last_success else {
+        return out;
+    };
+    family(&mut out, "climonitor_last_success_timestamp_seconds", "gauge", "Unix time of the last successful fetch.");
+    sample(&mut out, "climonitor_last_success_timestamp_seconds", &[], last_success);
+
+    let entries = &state.entries;
+    family(&mut out, "climonitor_connections", "gauge", "Connections in the listing.");
+    sample(&mut out, "climonitor_connections", &[], entries.len());
+
+    let breakdowns: [Breakdown; 4] = [
+        ("climonitor_connections_by_environment", "environment", |e| &e.environment),
+        ("climonitor_connections_by_server", "server", |e| &e.server),
+        ("climonitor_connections_by_thread_type", "thread_type", |e| &e.thread_type),
+        ("climonitor_connections_by_program", "program", |e| &e.function),
+    ];
+    for (name, label, field) in breakdowns {
+        let mut counts: BTreeMap<&str, u64> = BTreeMap::new();
+        for entry in entries {
+            *counts.entry(field(entry).trim()).or_default() += 1;
+        }
+        family(&mut out, name, "gauge", &format!("Connections by {}.", label.replace('_', " ")));
+        for (value, count) in counts {
+            sample(&mut out, name, &[(label, value)], count);
+        }
+    }
+
+    family(&mut out, "climonitor_memory_bytes", "gauge", "Memory used by all connections.");
+    sample(&mut out, "climonitor_memory_bytes", &[], entries.iter().map(|e| e.memory.max(0) as u64).sum::<u64>());
+
+    family(&mut out, "climonitor_instructions_per_second", "gauge", "Instructions per second of all connections.");
+    sample(&mut out, "climonitor_instructions_per_second", &[], entries.iter().map(|e| e.instructions_ps.max(0) as u64).sum::<u64>());
+
+    family(&mut out, "climonitor_idle_connections", "gauge", "Connections idle for longer than the threshold.");
+    for minutes in idle_minutes {
+        let count = entries.iter().filter(|e| e.inactive_secs().is_some_and(|secs| secs > minutes.saturating_mul(60))).count();
+        sample(&mut out, "climonitor_idle_connections", &[("above_minutes", &minutes.to_string())], count);
+    }
+
+    out
+}
+
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+        .collect();
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+    } else {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), value);
+    }
+}
+
+/// Escapes a label value: backslash, double quote and line feed.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, HashSet};
+
+use ratatui::widgets::TableState;
+
+use crate::{api_service::Entry, columns::Field, trends::format_bytes};
+
+
+/// Campos pelos quais a tabela pode ser agrupada, na ordem em que a tecla de agrupar os percorre.
+pub const GROUP_FIELDS: [Field; 4] = [Field::UserName, Field::MachineName, Field::Environment, Field::Function];
+
+/// Próximo agrupamento depois de `current`; depois do último volta à tabela sem grupos.
+pub fn next_field(current: Option<Field>) -> Option<Field> {
+    match current {
+        None => Some(GROUP_FIELDS[0]),
+        Some(field) => {
+            let position = GROUP_FIELDS.iter().position(|f| *f == field)?;
+            GROUP_FIELDS.get(position + 1).copied()
+        }
+    }
+}
+
+
+/// Conexões da listagem completa que têm o mesmo valor no campo agrupado.
+pub struct Group {
+    pub key: String,
+    pub members: Vec<Entry>,
+}
+
+impl Group {
+    pub fn ids(&self) -> Vec<String> {
+        self.members.iter().map(|e| e.id.clone()).collect()
+    }
+
+    /// Valor de uma coluna na linha do grupo: soma para memória e instruções, o maior para os
+    /// tempos, o próprio valor quando todos os membros concordam e a quantidade de valores
+    /// distintos quando não.
+    pub fn aggregate(&self, field: Field) -> String {
+        match field {
+            Field::Memory => format_bytes(self.members.iter().map(|e| e.memory.max(0) as u64).sum()),
+            Field::Instructions => self.members.iter().map(|e| e.instructions).sum::<i64>().to_string(),
+            Field::InstructionsPs => self.members.iter().map(|e| e.instructions_ps as i64).sum::<i64>().to_string(),
+            // Tempos mostram o maior do grupo.
+            Field::TimeUp | Field::InactiveTime => self.members
+                .iter()
+                .max_by(|a, b| field.compare(a, b))
+                .map(|e| field.value(e))
+                .unwrap_or_default(),
+            _ => {
+                let values: HashSet<String> = self.members.iter().map(|e| field.value(e)).collect();
+                match values.len() {
+                    1 => values.into_iter().next().unwrap_or_default(),
+                    n => format!("{} {}", n, plural(field)),
+                }
+            }
+        }
+    }
+}
+
+fn plural(field: Field) -> &'static str {
+    match field {
+        Field::UserName => "usuários",
+        Field::MachineName => "computadores",
+        Field::Function => "programas",
+        Field::Environment => "ambientes",
+        Field::ThreadType => "tipos",
+        Field::Server => "servidores",
+        _ => "valores",
+    }
+}
+
+
+/// Uma linha da tabela agrupada: o cabeçalho de um grupo ou uma conexão de um grupo aberto.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GroupRow {
+    Group(usize),
+    Member(usize, usize),
+}
+
+
+/// Estado da tabela agrupada.
+pub struct Grouping {
+    pub field: Field,
+    pub groups: Vec<Group>,
+    /// Chaves dos grupos abertos, para que continuem abertos depois de uma atualização.
+    pub expanded: HashSet<String>,
+    pub state: TableState,
+}
+
+impl Grouping {
+    pub fn new(field: Field, entries: &[Entry]) -> Self {
+        let mut grouping = Self {
+            field,
+            groups: Vec::new(),
+            expanded: HashSet::new(),
+            state: TableState::default().with_selected(Some(0)),
+        };
+        grouping.update(entries);
+        grouping
+    }
+
+    /// Refaz os grupos a partir da listagem completa, os maiores primeiro.
+    ///
+    /// O cursor continua na mesma linha (grupo ou conexão) se ela ainda existir.
+    pub fn update(&mut self, entries: &[Entry]) {
+        let current = self.current_key();
+
+        let mut groups: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+        for entry in entries {
+            groups.entry(self.field.value(entry)).or_default().push(entry.clone());
+        }
+        let mut groups: Vec<Group> = groups.into_iter().map(|(key, members)| Group { key, members }).collect();
+        groups.sort_by(|a, b| b.members.len().cmp(&a.members.len()).then_with(|| a.key.cmp(&b.key)));
+        self.groups = groups;
+
+        let rows = self.rows();
+        let index = current
+            .and_then(|key| rows.iter().position(|row| self.row_key(*row) == key))
+            .unwrap_or(self.state.selected().unwrap_or(0))
+            .min(rows.len().saturating_sub(1));
+        self.state.select(Some(index));
+    }
+
+    pub fn rows(&self) -> Vec<GroupRow> {
+        let mut rows = Vec::new();
+        for (g, group) in self.groups.iter().enumerate() {
+            rows.push(GroupRow::Group(g));
+            if self.expanded.contains(&group.key) {
+                rows.extend((0..group.members.len()).map(|m| GroupRow::Member(g, m)));
+            }
+        }
+        rows
+    }
+
+    fn row_key(&self, row: GroupRow) -> (String, Option<String>) {
+        match row {
+            GroupRow::Group(g) => (self.groups[g].key.clone(), None),
+            GroupRow::Member(g, m) => (self.groups[g].key.clone(), Some(self.groups[g].members[m].id.clone())),
+        }
+    }
+
+    fn current_key(&self) -> Option<(String, Option<String>)> {
+        self.current().map(|row| self.row_key(row))
+    }
+
+    pub fn current(&self) -> Option<GroupRow> {
+        self.rows().get(self.state.selected()?).copied()
+    }
+
+    /// Conexão sob o cursor, quando ele está em uma conexão e não no cabeçalho de um grupo.
+    pub fn current_entry(&self) -> Option<&Entry> {
+        match self.current()? {
+            GroupRow::Member(g, m) => Some(&self.groups[g].members[m]),
+            GroupRow::Group(_) => None,
+        }
+    }
+
+    /// Grupo sob o cursor, ou o grupo da conexão sob o cursor.
+    pub fn current_group(&self) -> Option<&Group> {
+        match self.current()? {
+            GroupRow::Group(g) | GroupRow::Member(g, _) => Some(&self.groups[g]),
+        }
+    }
+
+    pub fn move_cursor(&mut self, delta: isize, wrap: bool) {
+        let len = self.rows().len();
+        if len == 0 {
+            return;
+        }
+        let current = self.state.selected().unwrap_or(0) as isize;
+        let next = if wrap {
+            (current + delta).rem_euclid(len as isize)
+        } else {
+            (current + delta).clamp(0, len as isize - 1)
+        };
+        self.state.select(Some(next as usize));
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.rows().len() {
+            self.state.select(Some(index));
+        }
+    }
+
+    /// Abre ou fecha o grupo sob o cursor. Em uma conexão, fecha o grupo dela e volta ao cabeçalho.
+    pub fn toggle_expand(&mut self) {
+        let Some(row) = self.current() else { return };
+        let (GroupRow::Group(g) | GroupRow::Member(g, _)) = row;
+        let key = self.groups[g].key.clone();
+        if !self.expanded.remove(&key) {
+            self.expanded.insert(key);
+        }
+        if let Some(index) = self.rows().iter().position(|r| *r == GroupRow::Group(g)) {
+            self.state.select(Some(index));
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::{collections::BTreeMap, fs::{self, OpenOptions}, io::Write, path::{Path, PathBuf}};
+
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{api_service::Entry, config::{Config, HistoryConfig}, errors::HistoryError};
+
+
+/// Listagem completa de conexões em um instante.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub timestamp: String,
+    pub entries: Vec<Entry>,
+}
+
+impl Snapshot {
+    fn local_time(&self) -> Option<NaiveDateTime> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|t| t.with_timezone(&Local).naive_local())
+    }
+}
+
+
+/// Filters accepted by `climonitor history`.
+#[derive(Default)]
+pub struct HistoryQuery {
+    pub at: Option<NaiveDateTime>,
+    pub user: Option<String>,
+    pub since: Option<NaiveDateTime>,
+}
+
+
+/// Appends the listing to today's history file.
+///
+/// Creating a new day file also removes the files older than the configured retention.
+///
+/// # Errors
+///
+/// Returns a `HistoryError` if the directory or the file can't be written.
+pub fn record(config: &HistoryConfig, entries: &[Entry]) -> Result<(), HistoryError> {
+    fs::create_dir_all(&config.dir).map_err(|e| HistoryError::CreateDirError(e.to_string()))?;
+
+    let now = Local::now();
+    let path = day_file(&config.dir, now.date_naive());
+    let is_new_day = fs::metadata(&path).is_err();
+
+    let snapshot = Snapshot {
+        timestamp: now.to_rfc3339(),
+        entries: entries.to_vec(),
+    };
+    let line = serde_json::to_string(&snapshot).map_err(|e| HistoryError::Parsing(e.to_string()))?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| HistoryError::OpenFileError(e.to_string()))?;
+    writeln!(file, "{}", line).map_err(|e| HistoryError::WriteFileError(e.to_string()))?;
+
+    if is_new_day {
+        prune(config)?;
+    }
+    Ok(())
+}
+
+
+/// Removes the day files older than `retention_days`.
+pub fn prune(config: &HistoryConfig) -> Result<(), HistoryError> {
+    let today = Local::now().date_naive();
+    let Some(oldest) = today.checked_sub_days(Days::new(config.retention_days)) else {
+        return Ok(());
+    };
+
+    for (date, path) in day_files(&config.dir)? {
+        if date < oldest {
+            fs::remove_file(&path).map_err(|e| HistoryError::WriteFileError(e.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+
+fn day_file(dir: &str, date: NaiveDate) -> PathBuf {
+    Path::new(dir).join(format!("{}.jsonl", date.format("%Y-%m-%d")))
+}
+
+
+/// Lists the history files in `dir`, ordered by date.
+fn day_files(dir: &str) -> Result<BTreeMap<NaiveDate, PathBuf>, HistoryError> {
+    let mut files = BTreeMap::new();
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(files),
+        Err(e) => return Err(HistoryError::OpenFileError(e.to_string())),
+    };
+
+    for item in read_dir.flatten() {
+        let path = item.path();
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+        if let Ok(date) = NaiveDate::parse_from_str(stem, "%Y-%m-%d") {
+            files.insert(date, path);
+        }
+    }
+    Ok(files)
+}
+
+
+/// Loads every snapshot taken between `from` and `to` (inclusive), oldest first.
+fn load(dir: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<Snapshot>, HistoryError> {
+    let mut snapshots = Vec::new();
+    for (_, path) in day_files(dir)?.range(from..=to) {
+        let content = fs::read_to_string(path).map_err(|e| HistoryError::OpenFileError(e.to_string()))?;
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let snapshot: Snapshot = serde_json::from_str(line)
+                .map_err(|e| HistoryError::Parsing(format!("{} linha {}: {}", path.display(), i + 1, e)))?;
+            snapshots.push(snapshot);
+        }
+    }
+    Ok(snapshots)
+}
+
+
+/// Runs the `climonitor history` subcommand.
+///
+/// With `--at` prints the snapshot closest to (and not after) the given time. With `--user`
+/// prints every session of that user seen in the history, with the first and last time it
+/// appeared, which is when the user connected and logged off.
+pub fn print_query(config: &Config, query: &HistoryQuery) -> Result<(), HistoryError> {
+    let dir = &config.history.dir;
+
+    if let Some(at) = query.at {
+        let from = at.date().checked_sub_days(Days::new(1)).unwrap_or(at.date());
+        let snapshots = load(dir, from, at.date())?;
+        let Some(snapshot) = snapshots.iter().rev().find(|s| s.local_time().is_some_and(|t| t <= at)) else {
+            println!("Nenhum registro de histórico até {}.", at.format("%d/%m/%Y %H:%M"));
+            return Ok(());
+        };
+        print_snapshot(snapshot);
+        return Ok(());
+    }
+
+    if let Some(user) = &query.user {
+        let today = Local::now().date_naive();
+        let from = match query.since {
+            Some(since) => since.date(),
+            None => today.checked_sub_days(Days::new(config.history.retention_days)).unwrap_or(today),
+        };
+        let snapshots = load(dir, from, today)?;
+        print_user_sessions(&snapshots, user, query.since);
+        return Ok(());
+    }
+
+    println!("Informe --at ou --user.");
+    Ok(())
+}
+
+
+fn print_snapshot(snapshot: &Snapshot) {
+    let time = snapshot.local_time().map(|t| t.format("%d/%m/%Y %H:%M:%S").to_string()).unwrap_or_default();
+    println!("{}: {} conexões", time, snapshot.entries.len());
+
+    let mut by_environment: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut by_type: BTreeMap<&str, usize> = BTreeMap::new();
+    for entry in &snapshot.entries {
+        *by_environment.entry(entry.environment.trim()).or_default() += 1;
+        *by_type.entry(entry.thread_type.trim()).or_default() += 1;
+    }
+
+    println!("por ambiente:");
+    for (environment, count) in by_environment {
+        println!("    {:<20} {}", environment, count);
+    }
+    println!("por tipo de conexão:");
+    for (thread_type, count) in by_type {
+        println!("    {:<20} {}", thread_type, count);
+    }
+}
+
+
+fn print_user_sessions(snapshots: &[Snapshot], user: &str, since: Option<NaiveDateTime>) {
+    struct Session<'a> {
+        entry: &'a Entry,
+        first: NaiveDateTime,
+        last: NaiveDateTime,
+    }
+
+    let user = user.to_lowercase();
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut last_snapshot: Option<NaiveDateTime> = None;
+
+    for snapshot in snapshots {
+        let Some(time) = snapshot.local_time() else { continue };
+        if since.is_some_and(|since| time < since) {
+            continue;
+        }
+        last_snapshot = Some(time);
+        for entry in snapshot.entries.iter().filter(|e| e.user_name.trim().to_lowercase() == user) {
+            match sessions.iter_mut().find(|s| s.entry.id == entry.id) {
+                Some(session) => session.last = time,
+                None => sessions.push(Session { entry, first: time, last: time }),
+            }
+        }
+    }
+
+    if sessions.is_empty() {
+        println!("Nenhuma sessão encontrada para {}.", user);
+        return;
+    }
+
+    for session in sessions {
+        let status = if Some(session.last) == last_snapshot {
+            "ainda conectado".to_string()
+        } else {
+            format!("visto pela última vez em {}", session.last.format("%d/%m/%Y %H:%M:%S"))
+        };
+        println!(
+            "{} {} ({}) desde {} - {}",
+            session.entry.machine_name.trim(),
+            session.entry.function.trim(),
+            session.entry.environment.trim(),
+            session.first.format("%d/%m/%Y %H:%M:%S"),
+            status,
+        );
+    }
+}
@@ -0,0 +1,529 @@
+use std::{process::Stdio, sync::{Mutex, OnceLock}, time::{Duration, Instant}};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{api_service::Entry, audit::{self, AuditTarget}, config::Config, errors::HookError, protection};
+
+/// Tempo máximo de uma entrega, pela URL ou pelo comando.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Disparos recentes, para o intervalo mínimo entre disparos.
+static DEBOUNCE: Mutex<Debounce> = Mutex::new(Debounce { fired: Vec::new(), held: Vec::new() });
+/// Entregas que falharam desde a última consulta, para o monitor mostrar.
+static FAILURES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// Cliente HTTP das entregas, compartilhado entre os ganchos.
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+
+/// Eventos que disparam os ganchos.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    /// A quantidade de conexões passou de `connections_above` do gancho.
+    ConnectionsAbove,
+    /// Uma conexão protegida saiu da listagem.
+    ProtectedDisconnected,
+    /// Conexões foram desconectadas pelo monitor ou pela API.
+    Disconnect,
+    /// Uma mensagem foi enviada pelo monitor ou pela API.
+    Message,
+    /// A listagem deixou de responder.
+    ServerUnreachable,
+    /// A listagem voltou a responder.
+    ServerRecovered,
+    /// Disparo de `climonitor hooks test` sem evento.
+    #[serde(skip_deserializing)]
+    Test,
+}
+
+impl HookEvent {
+    pub const ALL: [HookEvent; 6] = [
+        HookEvent::ConnectionsAbove,
+        HookEvent::ProtectedDisconnected,
+        HookEvent::Disconnect,
+        HookEvent::Message,
+        HookEvent::ServerUnreachable,
+        HookEvent::ServerRecovered,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::ConnectionsAbove => "connections_above",
+            HookEvent::ProtectedDisconnected => "protected_disconnected",
+            HookEvent::Disconnect => "disconnect",
+            HookEvent::Message => "message",
+            HookEvent::ServerUnreachable => "server_unreachable",
+            HookEvent::ServerRecovered => "server_recovered",
+            HookEvent::Test => "test",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<HookEvent> {
+        HookEvent::ALL.into_iter().find(|e| e.name() == name)
+    }
+
+    /// Eventos sobre conexões específicas: dentro de `debounce_secs` só o mesmo conteúdo é
+    /// segurado, então desconexões e mensagens diferentes sempre chegam.
+    fn keyed_by_data(&self) -> bool {
+        matches!(self, HookEvent::Disconnect | HookEvent::Message | HookEvent::ProtectedDisconnected)
+    }
+}
+
+
+/// Gancho de `[[hooks]]` no config.toml.
+///
+/// Para cada evento de `events`, faz um POST do JSON do evento em `url` ou roda `command` com o
+/// JSON na entrada padrão e o nome do evento em `CLIMONITOR_EVENT`. Dentro de `debounce_secs`,
+/// `connections_above` e `server_unreachable` não disparam o gancho de novo (nem o
+/// `server_recovered` de uma queda segurada), e `disconnect`, `message` e
+/// `protected_disconnected` só são segurados quando repetem o mesmo conteúdo.
+///
+/// ```toml
+/// [[hooks]]
+/// events = ["connections_above", "server_unreachable"]
+/// url = "http://ponte-teams/hook"
+/// connections_above = 500
+///
+/// [[hooks]]
+/// events = ["disconnect", "protected_disconnected"]
+/// command = "./notificar.sh"
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Hook {
+    pub events: Vec<HookEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    pub debounce_secs: u64,
+    /// Limite do evento `connections_above` deste gancho.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connections_above: Option<usize>,
+}
+
+impl Default for Hook {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            url: None,
+            command: None,
+            debounce_secs: 300,
+            connections_above: None,
+        }
+    }
+}
+
+impl Hook {
+    /// URL ou comando, para as mensagens de erro.
+    pub fn describe(&self) -> String {
+        match (&self.url, &self.command) {
+            (Some(url), _) => url.clone(),
+            (None, Some(command)) => command.clone(),
+            (None, None) => "sem url nem command".to_string(),
+        }
+    }
+}
+
+
+/// Evento a disparar: evento, resumo, dados e o único gancho que o recebe, quando for o caso.
+type Pending = (HookEvent, String, Value, Option<usize>);
+
+
+/// Acompanha as atualizações da listagem para disparar os eventos de mudança de estado.
+#[derive(Default)]
+pub struct Watch {
+    /// Se cada gancho já estava acima do seu `connections_above`.
+    above: Vec<bool>,
+    unreachable: bool,
+    /// Conexões protegidas da listagem anterior; `None` antes da primeira.
+    protected: Option<Vec<AuditTarget>>,
+}
+
+impl Watch {
+    /// Compara a listagem nova com a anterior e dispara `server_recovered`, `connections_above` e
+    /// `protected_disconnected`.
+    pub fn listing(&mut self, config: &Config, entries: &[Entry]) {
+        for (event, summary, data, only) in self.listing_events(config, entries) {
+            dispatch(config, event, summary, data, only);
+        }
+    }
+
+    /// Eventos da listagem nova, com o gancho a que se limitam quando for o caso.
+    fn listing_events(&mut self, config: &Config, entries: &[Entry]) -> Vec<Pending> {
+        let mut events = Vec::new();
+        if self.unreachable {
+            self.unreachable = false;
+            events.push((HookEvent::ServerRecovered, "A listagem do Protheus voltou a responder.".to_string(), json!({}), None));
+        }
+
+        self.above.resize(config.hooks.len(), false);
+        for (i, hook) in config.hooks.iter().enumerate() {
+            let Some(limit) = hook.connections_above else { continue };
+            let above = entries.len() > limit;
+            if above && !self.above[i] {
+                let summary = format!("{} conexões, acima do limite de {}.", entries.len(), limit);
+                events.push((HookEvent::ConnectionsAbove, summary, json!({ "connections": entries.len(), "limit": limit }), Some(i)));
+            }
+            self.above[i] = above;
+        }
+
+        let protected: Vec<AuditTarget> = entries
+            .iter()
+            .filter(|e| protection::is_protected_entry(&config.protected, e))
+            .map(AuditTarget::from_entry)
+            .collect();
+        if let Some(previous) = self.protected.take() {
+            let gone: Vec<AuditTarget> = previous.into_iter().filter(|p| !protected.iter().any(|t| t.id == p.id)).collect();
+            if !gone.is_empty() {
+                let summary = format!("Conexões protegidas saíram: {}", describe(&gone));
+                events.push((HookEvent::ProtectedDisconnected, summary, json!({ "connections": gone }), None));
+            }
+        }
+        self.protected = Some(protected);
+        events
+    }
+
+    /// Registra uma falha ao buscar a listagem e dispara `server_unreachable` na primeira delas.
+    pub fn failure(&mut self, config: &Config, error: &str) {
+        if !self.unreachable {
+            self.unreachable = true;
+            let summary = format!("A listagem do Protheus não responde: {}", error);
+            fire(config, HookEvent::ServerUnreachable, summary, json!({ "error": error }));
+        }
+    }
+}
+
+
+/// Fires `event` on every hook subscribed to it, in the background.
+///
+/// `summary` is a line of text for chat bridges and `data` the event details. Hooks are skipped
+/// within `debounce_secs` as described in `Hook`. Failed deliveries are kept for `take_failures`.
+pub fn fire(config: &Config, event: HookEvent, summary: String, data: Value) {
+    dispatch(config, event, summary, data, None);
+}
+
+fn dispatch(config: &Config, event: HookEvent, summary: String, data: Value, only: Option<usize>) {
+    if config.hooks.is_empty() {
+        return;
+    }
+    let payload = payload(config, event, &summary, data);
+    for (i, hook) in config.hooks.iter().enumerate() {
+        if only.is_some_and(|only| only != i) || !hook.events.contains(&event) {
+            continue;
+        }
+        let allowed = DEBOUNCE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .allow(i, event, &data_key(event, &payload), hook.debounce_secs, Instant::now());
+        if !allowed {
+            continue;
+        }
+        let hook = hook.clone();
+        let payload = payload.clone();
+        tokio::spawn(async move {
+            if let Err(e) = deliver(&hook, event, &payload).await {
+                FAILURES.lock().unwrap_or_else(|e| e.into_inner()).push(e.to_string());
+            }
+        });
+    }
+}
+
+
+/// What two firings must share to be debounced together: the event details for events about
+/// specific connections, nothing else for the state changes.
+fn data_key(event: HookEvent, payload: &Value) -> String {
+    if event.keyed_by_data() {
+        payload["data"].to_string()
+    } else {
+        String::new()
+    }
+}
+
+
+/// Disparos recentes de cada gancho.
+struct Debounce {
+    /// Gancho, evento, conteúdo, quando disparou e por quanto tempo um disparo igual é segurado.
+    fired: Vec<(usize, HookEvent, String, Instant, Duration)>,
+    /// Ganchos cujo último `server_unreachable` foi segurado; o `server_recovered` seguinte
+    /// também é, para a ponte não receber uma volta sem a queda.
+    held: Vec<usize>,
+}
+
+impl Debounce {
+    /// Returns whether hook `index` may fire `event` with `key` at `now`, and if so records it.
+    fn allow(&mut self, index: usize, event: HookEvent, key: &str, secs: u64, now: Instant) -> bool {
+        if event == HookEvent::ServerRecovered {
+            let held = self.held.contains(&index);
+            self.held.retain(|i| *i != index);
+            return !held;
+        }
+
+        self.fired.retain(|(_, _, _, at, window)| now.saturating_duration_since(*at) < *window);
+        let repeated = self.fired.iter().any(|(i, e, k, _, _)| *i == index && *e == event && k == key);
+        if event == HookEvent::ServerUnreachable {
+            self.held.retain(|i| *i != index);
+            if repeated {
+                self.held.push(index);
+            }
+        }
+        if !repeated {
+            self.fired.push((index, event, key.to_string(), now, Duration::from_secs(secs)));
+        }
+        !repeated
+    }
+}
+
+
+/// Deliveries that failed since the last call.
+pub fn take_failures() -> Vec<String> {
+    std::mem::take(&mut *FAILURES.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+
+/// JSON sent to the hooks. `text` carries the summary, so Slack and Teams style incoming webhooks
+/// can show it as is.
+fn payload(config: &Config, event: HookEvent, summary: &str, data: Value) -> Value {
+    json!({
+        "event": event.name(),
+        "text": format!("[CLIMonitor {}] {}", config.enviorment, summary),
+        "timestamp": Local::now().to_rfc3339(),
+        "server": format!("{}:{}", config.ip, config.porta),
+        "environment": config.enviorment,
+        "os_user": audit::os_user(),
+        "data": data,
+    })
+}
+
+
+/// Posts the payload to the hook URL, or runs the hook command with the payload on stdin.
+async fn deliver(hook: &Hook, event: HookEvent, payload: &Value) -> Result<(), HookError> {
+    let body = payload.to_string();
+    let fail = |e: String| HookError::Delivery(hook.describe(), e);
+
+    if let Some(url) = &hook.url {
+        let resp = CLIENT
+            .get_or_init(reqwest::Client::new)
+            .post(url)
+            .timeout(DELIVERY_TIMEOUT)
+            .header("Content-Type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| fail(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(fail(format!("status {}", resp.status())));
+        }
+        return Ok(());
+    }
+
+    let Some(command) = &hook.command else {
+        return Err(HookError::Config(hook.describe()));
+    };
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut child = Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .env("CLIMONITOR_EVENT", event.name())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| fail(e.to_string()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Um comando que não lê a entrada não é um erro.
+        let _ = stdin.write_all(body.as_bytes()).await;
+    }
+    let status = tokio::time::timeout(DELIVERY_TIMEOUT, child.wait())
+        .await
+        .map_err(|_| fail(format!("sem resposta em {}s", DELIVERY_TIMEOUT.as_secs())))?
+        .map_err(|e| fail(e.to_string()))?;
+    if !status.success() {
+        return Err(fail(status.to_string()));
+    }
+    Ok(())
+}
+
+
+/// Runs `climonitor hooks test`: delivers a sample of `event` to the hooks subscribed to it, or a
+/// `test` event to every hook, waiting for each one and printing the result.
+///
+/// Debounce does not apply.
+pub async fn test(config: &Config, event: Option<HookEvent>) -> Result<(), HookError> {
+    let hooks: Vec<(usize, &Hook)> = config
+        .hooks
+        .iter()
+        .enumerate()
+        .filter(|(_, hook)| event.is_none_or(|e| hook.events.contains(&e)))
+        .collect();
+    if hooks.is_empty() {
+        println!("Nenhum gancho configurado para este evento em [[hooks]].");
+        return Ok(());
+    }
+
+    let event = event.unwrap_or(HookEvent::Test);
+    let (summary, data) = sample(event);
+    let payload = payload(config, event, &summary, data);
+    let mut failed = 0;
+    for (i, hook) in hooks {
+        match deliver(hook, event, &payload).await {
+            Ok(()) => println!("gancho {} ({}): ok", i + 1, hook.describe()),
+            Err(e) => {
+                failed += 1;
+                println!("gancho {}: {}", i + 1, e);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(HookError::Failed(failed));
+    }
+    Ok(())
+}
+
+
+/// Summary and data of a made-up `event`, for `hooks test`.
+fn sample(event: HookEvent) -> (String, Value) {
+    let target = AuditTarget {
+        id: "0".to_string(),
+        user_name: "TESTE".to_string(),
+        machine_name: "PC-TESTE".to_string(),
+        function: "SIGAADV".to_string(),
+        environment: "P12".to_string(),
+        thread_type: "SmartClient".to_string(),
+        time_up: "01:00:00".to_string(),
+        inactive_time: "00:30:00".to_string(),
+    };
+    let summary = format!("Teste do evento {}.", event.name());
+    let data = match event {
+        HookEvent::ConnectionsAbove => json!({ "connections": 501, "limit": 500 }),
+        HookEvent::ProtectedDisconnected => json!({ "connections": [target] }),
+        HookEvent::Disconnect => json!({ "targets": [target], "forced": false, "api_client": null }),
+        HookEvent::Message => json!({ "targets": [target], "message": "mensagem de teste", "api_client": null }),
+        HookEvent::ServerUnreachable => json!({ "error": "teste" }),
+        HookEvent::ServerRecovered | HookEvent::Test => json!({}),
+    };
+    (summary, data)
+}
+
+
+/// Descrições das conexões, para os resumos.
+pub fn describe(targets: &[AuditTarget]) -> String {
+    targets.iter().map(|t| t.describe()).collect::<Vec<_>>().join(", ")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(extra: &str) -> Config {
+        let base = "login = \"a\"\npassword = \"b\"\nenviorment = \"P12\"\nrefresh_interval_in_secs = 2\n\
+                    ip = \"127.0.0.1\"\nporta = \"8080\"\nrequest_timeout_in_secs = 1\n";
+        toml::from_str(&format!("{}{}", base, extra)).unwrap()
+    }
+
+    fn entry(id: &str, user_name: &str) -> Entry {
+        Entry { id: id.to_string(), user_name: user_name.to_string(), ..Default::default() }
+    }
+
+    fn events(pending: &[Pending]) -> Vec<(HookEvent, Option<usize>)> {
+        pending.iter().map(|(event, _, _, only)| (*event, *only)).collect()
+    }
+
+    fn debounce() -> Debounce {
+        Debounce { fired: Vec::new(), held: Vec::new() }
+    }
+
+    #[test]
+    fn state_events_are_held_within_the_window() {
+        let mut debounce = debounce();
+        let start = Instant::now();
+        let later = |secs| start + Duration::from_secs(secs);
+        assert!(debounce.allow(0, HookEvent::ConnectionsAbove, "", 300, start));
+        assert!(!debounce.allow(0, HookEvent::ConnectionsAbove, "", 300, later(299)));
+        // Outro gancho tem a sua própria janela.
+        assert!(debounce.allow(1, HookEvent::ConnectionsAbove, "", 300, later(299)));
+        assert!(debounce.allow(0, HookEvent::ConnectionsAbove, "", 300, later(300)));
+    }
+
+    #[test]
+    fn distinct_actions_are_always_delivered() {
+        let mut debounce = debounce();
+        let now = Instant::now();
+        assert!(debounce.allow(0, HookEvent::Disconnect, "{\"id\":1}", 300, now));
+        assert!(debounce.allow(0, HookEvent::Disconnect, "{\"id\":2}", 300, now));
+        assert!(!debounce.allow(0, HookEvent::Disconnect, "{\"id\":1}", 300, now));
+        assert!(debounce.allow(0, HookEvent::Message, "{\"id\":1}", 300, now));
+    }
+
+    #[test]
+    fn recovery_of_a_held_outage_is_held_too() {
+        let mut debounce = debounce();
+        let start = Instant::now();
+        let later = |secs| start + Duration::from_secs(secs);
+        assert!(debounce.allow(0, HookEvent::ServerUnreachable, "", 300, start));
+        assert!(debounce.allow(0, HookEvent::ServerRecovered, "", 300, later(10)));
+        assert!(!debounce.allow(0, HookEvent::ServerUnreachable, "", 300, later(20)));
+        assert!(!debounce.allow(0, HookEvent::ServerRecovered, "", 300, later(30)));
+        assert!(debounce.allow(0, HookEvent::ServerUnreachable, "", 300, later(400)));
+        assert!(debounce.allow(0, HookEvent::ServerRecovered, "", 300, later(410)));
+    }
+
+    #[test]
+    fn zero_window_never_holds() {
+        let mut debounce = debounce();
+        let now = Instant::now();
+        assert!(debounce.allow(0, HookEvent::ServerUnreachable, "", 0, now));
+        assert!(debounce.allow(0, HookEvent::ServerUnreachable, "", 0, now));
+        assert!(debounce.allow(0, HookEvent::ServerRecovered, "", 0, now));
+    }
+
+    #[test]
+    fn huge_window_does_not_overflow() {
+        let mut debounce = debounce();
+        let now = Instant::now();
+        assert!(debounce.allow(0, HookEvent::ConnectionsAbove, "", u64::MAX, now));
+        assert!(!debounce.allow(0, HookEvent::ConnectionsAbove, "", u64::MAX, now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn connections_above_fires_when_crossing_the_limit() {
+        let config = config("[[hooks]]\nevents = [\"connections_above\"]\nurl = \"http://x\"\nconnections_above = 1\n");
+        let mut watch = Watch::default();
+        let one = vec![entry("1", "ANA")];
+        let two = vec![entry("1", "ANA"), entry("2", "JOAO")];
+
+        assert!(watch.listing_events(&config, &one).is_empty());
+        assert_eq!(events(&watch.listing_events(&config, &two)), [(HookEvent::ConnectionsAbove, Some(0))]);
+        assert!(watch.listing_events(&config, &two).is_empty());
+        assert!(watch.listing_events(&config, &one).is_empty());
+        assert_eq!(events(&watch.listing_events(&config, &two)), [(HookEvent::ConnectionsAbove, Some(0))]);
+    }
+
+    #[test]
+    fn protected_connection_leaving_fires_once() {
+        let config = config("[[protected]]\nuser_name = \"JOB*\"\n");
+        let mut watch = Watch::default();
+        let both = vec![entry("1", "JOB01"), entry("2", "ANA")];
+        let without_job = vec![entry("2", "ANA")];
+
+        assert!(watch.listing_events(&config, &both).is_empty());
+        let pending = watch.listing_events(&config, &without_job);
+        assert_eq!(events(&pending), [(HookEvent::ProtectedDisconnected, None)]);
+        assert_eq!(pending[0].2["connections"][0]["id"], "1");
+        assert!(watch.listing_events(&config, &without_job).is_empty());
+    }
+
+    #[test]
+    fn recovery_follows_a_failure() {
+        let config = config("");
+        let mut watch = Watch::default();
+        watch.failure(&config, "timeout");
+        assert_eq!(events(&watch.listing_events(&config, &[])), [(HookEvent::ServerRecovered, None)]);
+        assert!(watch.listing_events(&config, &[]).is_empty());
+    }
+}
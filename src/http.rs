@@ -0,0 +1,301 @@
+use std::{future::Future, time::Duration};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::errors::HttpError;
+
+/// Maior corpo de requisição aceito.
+const MAX_BODY: usize = 64 * 1024;
+/// Maior quantidade de cabeçalhos aceita.
+const MAX_HEADERS: usize = 100;
+/// Maior linha aceita, da requisição ou de um cabeçalho.
+const MAX_LINE: usize = 8 * 1024;
+/// Tempo máximo para o cliente enviar a requisição inteira.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+
+/// Requisição HTTP/1.1 recebida, já com o corpo lido.
+pub struct Request {
+    /// Endereço de quem fez a requisição.
+    pub peer: String,
+    pub method: String,
+    pub path: String,
+    /// Parâmetros da query string, já decodificados.
+    pub query: Vec<(String, String)>,
+    /// Cabeçalhos com o nome em minúsculas.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: impl Into<String>) -> Self {
+        Self { status, content_type, body: body.into() }
+    }
+
+    pub fn text(status: u16, body: impl Into<String>) -> Self {
+        Self::new(status, "text/plain; charset=utf-8", body)
+    }
+
+    pub fn json(status: u16, body: &serde_json::Value) -> Self {
+        Self::new(status, "application/json", body.to_string())
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            502 => "Bad Gateway",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+
+/// Accepts connections on `listen` forever, answering each request with `handler`.
+///
+/// Every connection runs on its own task and is closed after one response; this is only meant
+/// for scrapers and scripts, not browsers.
+///
+/// # Errors
+///
+/// Returns `HttpError::Bind` if the address can't be listened on.
+pub async fn serve<H, F>(listen: &str, handler: H) -> Result<(), HttpError>
+where
+    H: Fn(Request) -> F + Clone + Send + 'static,
+    F: Future<Output = Response> + Send,
+{
+    let listener = TcpListener::bind(listen).await.map_err(|e| HttpError::Bind(format!("{}: {}", listen, e)))?;
+    loop {
+        let Ok((stream, peer)) = listener.accept().await else {
+            continue;
+        };
+        let handler = handler.clone();
+        tokio::spawn(async move {
+            let _ = handle(stream, peer.to_string(), handler).await;
+        });
+    }
+}
+
+
+async fn handle<H, F>(mut stream: TcpStream, peer: String, handler: H) -> std::io::Result<()>
+where
+    H: Fn(Request) -> F,
+    F: Future<Output = Response>,
+{
+    let response = match tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream, peer)).await {
+        Ok(Ok(request)) => handler(request).await,
+        Ok(Err(e)) => Response::text(e.status(), e.to_string()),
+        Err(_) => return Ok(()),
+    };
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len(),
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+
+async fn read_request(stream: &mut TcpStream, peer: String) -> Result<Request, HttpError> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    read_line(&mut reader, &mut line).await?;
+
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(HttpError::BadRequest(line.trim().to_string()));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        peer,
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let (name, value) = p.split_once('=').unwrap_or((p, ""));
+                (decode(name), decode(value))
+            })
+            .collect(),
+        headers: Vec::new(),
+        body: Vec::new(),
+    };
+
+    loop {
+        line.clear();
+        read_line(&mut reader, &mut line).await?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if request.headers.len() == MAX_HEADERS {
+            return Err(HttpError::BadRequest("cabeçalhos demais".to_string()));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            request.headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let length: usize = match request.header("content-length") {
+        Some(value) => value.parse().map_err(|_| HttpError::BadRequest(format!("Content-Length {}", value)))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(HttpError::TooLarge(length));
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body).await.map_err(|e| HttpError::BadRequest(e.to_string()))?;
+    Ok(request)
+}
+
+
+/// Reads one line into `line`, refusing lines longer than `MAX_LINE` without buffering them whole.
+async fn read_line(reader: &mut BufReader<&mut TcpStream>, line: &mut String) -> Result<(), HttpError> {
+    reader
+        .take(MAX_LINE as u64 + 1)
+        .read_line(line)
+        .await
+        .map_err(|e| HttpError::BadRequest(e.to_string()))?;
+    if line.len() > MAX_LINE {
+        return Err(HttpError::LineTooLong(MAX_LINE));
+    }
+    Ok(())
+}
+
+
+/// Decodes the `+` and `%XX` escapes of a query string component.
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (b'+', _) => out.push(b' '),
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 2;
+            }
+            (b, _) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends `raw` through a local socket and reads it back as a request.
+    async fn read(raw: Vec<u8>) -> Result<Request, HttpError> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (mut stream, peer) = listener.accept().await.unwrap();
+        client.write_all(&raw).await.unwrap();
+        client.shutdown().await.unwrap();
+        read_request(&mut stream, peer.to_string()).await
+    }
+
+    #[test]
+    fn decodes_plus_and_percent_escapes() {
+        assert_eq!(decode("a+b%20c"), "a b c");
+        assert_eq!(decode("jos%C3%A9"), "josé");
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%zz%4"), "%zz%4");
+        assert_eq!(decode("%é"), "%é");
+    }
+
+    #[tokio::test]
+    async fn reads_the_request_line_query_headers_and_body() {
+        let request = read(b"POST /connections/kill?user=jos%C3%A9&x HTTP/1.1\r\nX-Client: Ops\r\nContent-Length: 4\r\n\r\nbody".to_vec()).await.ok().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/connections/kill");
+        assert_eq!(request.param("user"), Some("josé"));
+        assert_eq!(request.param("x"), Some(""));
+        assert_eq!(request.header("x-client"), Some("Ops"));
+        assert_eq!(request.body, b"body");
+    }
+
+    #[tokio::test]
+    async fn refuses_a_line_longer_than_the_limit() {
+        let mut raw = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE)).into_bytes();
+        let e = read(raw.clone()).await.err().unwrap();
+        assert_eq!(e.status(), 431);
+
+        raw = format!("GET / HTTP/1.1\r\nX-Long: {}\r\n\r\n", "a".repeat(MAX_LINE)).into_bytes();
+        let e = read(raw).await.err().unwrap();
+        assert_eq!(e.status(), 431);
+    }
+
+    #[tokio::test]
+    async fn accepts_a_line_at_the_limit() {
+        let path = "a".repeat(MAX_LINE - "GET / HTTP/1.1\r\n".len());
+        let request = read(format!("GET /{} HTTP/1.1\r\n\r\n", path).into_bytes()).await.ok().unwrap();
+        assert_eq!(request.path.len(), path.len() + 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_a_body_larger_than_the_limit() {
+        let e = read(format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1).into_bytes()).await.err().unwrap();
+        assert_eq!(e.status(), 413);
+
+        let e = read(b"POST / HTTP/1.1\r\nContent-Length: muito\r\n\r\n".to_vec()).await.err().unwrap();
+        assert_eq!(e.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn refuses_too_many_headers() {
+        let headers = "X-A: 1\r\n".repeat(MAX_HEADERS + 1);
+        let e = read(format!("GET / HTTP/1.1\r\n{}\r\n", headers).into_bytes()).await.err().unwrap();
+        assert_eq!(e.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn answers_the_read_error_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (stream, peer) = listener.accept().await.unwrap();
+        client.write_all(format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE)).as_bytes()).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        handle(stream, peer.to_string(), |_| async { Response::text(200, "ok") }).await.unwrap();
+        let mut answer = String::new();
+        client.read_to_string(&mut answer).await.unwrap();
+        assert!(answer.starts_with("HTTP/1.1 431 "), "{}", answer);
+    }
+}
@@ -0,0 +1,619 @@
+use std::collections::BTreeMap;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+
+/// Onde uma tecla é interpretada. Teclas iguais em contextos diferentes não conflitam.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Context {
+    Table,
+    Confirm,
+}
+
+impl Context {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Context::Table => "tabela",
+            Context::Confirm => "confirmação",
+        }
+    }
+}
+
+
+/// Ações que podem ser associadas a teclas na seção `[keys]` do config.toml.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Quit,
+    Up,
+    Down,
+    PrevPage,
+    NextPage,
+    Refresh,
+    Delete,
+    Message,
+    Details,
+    Detail,
+    Audit,
+    Dashboard,
+    Columns,
+    GroupBy,
+    Duplicates,
+    KillDuplicates,
+    Filter,
+    Alerts,
+    Export,
+    ToggleSelect,
+    ClearSelection,
+    MultiSelect,
+    ExtendUp,
+    ExtendDown,
+    SelectAll,
+    InvertSelection,
+    SelectUser,
+    SelectProgram,
+    ReviewSelection,
+    Help,
+    Palette,
+    Confirm,
+    Cancel,
+}
+
+impl Action {
+    pub const ALL: [Action; 33] = [
+        Action::Quit,
+        Action::Up,
+        Action::Down,
+        Action::PrevPage,
+        Action::NextPage,
+        Action::Refresh,
+        Action::Delete,
+        Action::Message,
+        Action::Details,
+        Action::Detail,
+        Action::Audit,
+        Action::Dashboard,
+        Action::Columns,
+        Action::GroupBy,
+        Action::Duplicates,
+        Action::KillDuplicates,
+        Action::Filter,
+        Action::Alerts,
+        Action::Export,
+        Action::ToggleSelect,
+        Action::ClearSelection,
+        Action::MultiSelect,
+        Action::ExtendUp,
+        Action::ExtendDown,
+        Action::SelectAll,
+        Action::InvertSelection,
+        Action::SelectUser,
+        Action::SelectProgram,
+        Action::ReviewSelection,
+        Action::Help,
+        Action::Palette,
+        Action::Confirm,
+        Action::Cancel,
+    ];
+
+    /// Nome da ação na seção `[keys]`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PrevPage => "prev_page",
+            Action::NextPage => "next_page",
+            Action::Refresh => "refresh",
+            Action::Delete => "delete",
+            Action::Message => "message",
+            Action::Details => "details",
+            Action::Detail => "detail",
+            Action::Audit => "audit",
+            Action::Dashboard => "dashboard",
+            Action::Columns => "columns",
+            Action::GroupBy => "group_by",
+            Action::Duplicates => "duplicates",
+            Action::KillDuplicates => "kill_duplicates",
+            Action::Filter => "filter",
+            Action::Alerts => "alerts",
+            Action::Export => "export",
+            Action::ToggleSelect => "toggle_select",
+            Action::ClearSelection => "clear_selection",
+            Action::MultiSelect => "multi_select",
+            Action::ExtendUp => "extend_up",
+            Action::ExtendDown => "extend_down",
+            Action::SelectAll => "select_all",
+            Action::InvertSelection => "invert_selection",
+            Action::SelectUser => "select_user",
+            Action::SelectProgram => "select_program",
+            Action::ReviewSelection => "review_selection",
+            Action::Help => "help",
+            Action::Palette => "palette",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+        }
+    }
+
+    /// Descrição exibida no rodapé e na ajuda.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Sair",
+            Action::Up => "Sobe",
+            Action::Down => "Desce",
+            Action::PrevPage => "Página anterior",
+            Action::NextPage => "Próxima página",
+            Action::Refresh => "Atualizar",
+            Action::Delete => "Desconectar",
+            Action::Message => "Mensagem",
+            Action::Details => "Mais detalhes",
+            Action::Detail => "Acompanhar",
+            Action::Audit => "Auditoria",
+            Action::Dashboard => "Gráficos",
+            Action::Columns => "Colunas",
+            Action::GroupBy => "Agrupar",
+            Action::Duplicates => "Duplicadas",
+            Action::KillDuplicates => "Derruba duplicadas",
+            Action::Filter => "Filtrar",
+            Action::Alerts => "Alertas",
+            Action::Export => "Exportar",
+            Action::ToggleSelect => "Des/Seleciona",
+            Action::ClearSelection => "Limpa seleção",
+            Action::MultiSelect => "Seleciona varios",
+            Action::ExtendUp => "Estende seleção acima",
+            Action::ExtendDown => "Estende seleção abaixo",
+            Action::SelectAll => "Marca visíveis",
+            Action::InvertSelection => "Inverte seleção",
+            Action::SelectUser => "Marca do usuário",
+            Action::SelectProgram => "Marca do programa",
+            Action::ReviewSelection => "Revisa seleção",
+            Action::Help => "Ajuda",
+            Action::Palette => "Comandos",
+            Action::Confirm => "Confirma",
+            Action::Cancel => "Cancela",
+        }
+    }
+
+    /// Nome na paleta de comandos, ou `None` para as ações que não aparecem nela.
+    pub fn title(&self) -> Option<&'static str> {
+        match self {
+            Action::Quit => Some("sair do monitor"),
+            Action::PrevPage => Some("página anterior"),
+            Action::NextPage => Some("próxima página"),
+            Action::Refresh => Some("atualizar listagem"),
+            Action::Delete => Some("desconectar selecionados"),
+            Action::Message => Some("enviar mensagem"),
+            Action::Details => Some("mais detalhes da conexão"),
+            Action::Detail => Some("acompanhar conexão em tela cheia"),
+            Action::Audit => Some("abrir log de auditoria"),
+            Action::Dashboard => Some("mostrar/esconder gráficos"),
+            Action::Columns => Some("escolher colunas"),
+            Action::GroupBy => Some("agrupar por usuário, computador, ambiente ou programa"),
+            Action::Duplicates => Some("mostrar só sessões duplicadas"),
+            Action::KillDuplicates => Some("desconectar sessões duplicadas mais antigas"),
+            Action::Filter => Some("filtrar conexões por texto"),
+            Action::Alerts => Some("histórico de alertas"),
+            Action::Export => Some("exportar para CSV, JSON ou HTML"),
+            Action::ToggleSelect => Some("selecionar/desmarcar linha"),
+            Action::ClearSelection => Some("limpar seleção"),
+            Action::MultiSelect => Some("modo de seleção múltipla"),
+            Action::SelectAll => Some("marcar todas as linhas visíveis"),
+            Action::InvertSelection => Some("inverter seleção"),
+            Action::SelectUser => Some("selecionar todos deste usuário"),
+            Action::SelectProgram => Some("selecionar todos deste programa"),
+            Action::ReviewSelection => Some("revisar selecionadas"),
+            Action::Help => Some("ajuda"),
+            Action::Up | Action::Down | Action::ExtendUp | Action::ExtendDown | Action::Palette | Action::Confirm | Action::Cancel => None,
+        }
+    }
+
+    /// Grupo em que a ação aparece na ajuda.
+    pub fn group(&self) -> &'static str {
+        match self {
+            Action::Up | Action::Down | Action::PrevPage | Action::NextPage => "Navegação",
+            Action::ToggleSelect
+            | Action::ClearSelection
+            | Action::MultiSelect
+            | Action::ExtendUp
+            | Action::ExtendDown
+            | Action::SelectAll
+            | Action::InvertSelection
+            | Action::SelectUser
+            | Action::SelectProgram
+            | Action::ReviewSelection => "Seleção",
+            Action::Confirm | Action::Cancel => "Modal de desconexão",
+            _ => "Tabela",
+        }
+    }
+
+    pub fn context(&self) -> Context {
+        match self {
+            Action::Confirm | Action::Cancel => Context::Confirm,
+            _ => Context::Table,
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Up => &["up"],
+            Action::Down => &["down"],
+            Action::PrevPage => &["left"],
+            Action::NextPage => &["right"],
+            Action::Refresh => &["a"],
+            Action::Delete => &["d"],
+            Action::Message => &["m"],
+            Action::Details => &["M"],
+            Action::Detail => &["enter"],
+            Action::Audit => &["l"],
+            Action::Dashboard => &["g"],
+            Action::Columns => &["c"],
+            Action::GroupBy => &["G"],
+            Action::Duplicates => &["D"],
+            Action::KillDuplicates => &["K"],
+            Action::Filter => &["/"],
+            Action::Alerts => &["!"],
+            Action::Export => &["x"],
+            Action::ToggleSelect => &["e"],
+            Action::ClearSelection => &["E"],
+            Action::MultiSelect => &["tab"],
+            Action::ExtendUp => &["shift-up"],
+            Action::ExtendDown => &["shift-down"],
+            Action::SelectAll => &["A"],
+            Action::InvertSelection => &["i"],
+            Action::SelectUser => &["u"],
+            Action::SelectProgram => &["p"],
+            Action::ReviewSelection => &["v"],
+            Action::Help => &["?"],
+            Action::Palette => &[":"],
+            Action::Confirm => &["s"],
+            Action::Cancel => &["n"],
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
+
+/// Valor de uma ação no config.toml: uma tecla (`delete = "x"`) ou várias (`down = ["down", "j"]`).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeySpec {
+    fn keys(&self) -> Vec<&str> {
+        match self {
+            KeySpec::One(key) => vec![key.as_str()],
+            KeySpec::Many(keys) => keys.iter().map(|k| k.as_str()).collect(),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Lê uma tecla no formato do config.toml, como `d`, `M`, `tab`, `ctrl-d` ou `alt-left`.
+    pub fn parse(value: &str) -> Option<KeyBinding> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = value;
+        loop {
+            let lower = rest.to_lowercase();
+            if lower.starts_with("ctrl-") {
+                modifiers |= KeyModifiers::CONTROL;
+            } else if lower.starts_with("alt-") {
+                modifiers |= KeyModifiers::ALT;
+            } else if lower.starts_with("shift-") {
+                modifiers |= KeyModifiers::SHIFT;
+            } else {
+                break;
+            }
+            rest = &rest[rest.find('-')? + 1..];
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "tab" => KeyCode::Tab,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            f if f.len() > 1 && f.starts_with('f') => KeyCode::F(f[1..].parse().ok()?),
+            _ => {
+                let mut chars = rest.chars();
+                let c = chars.next()?;
+                if chars.next().is_some() {
+                    return None;
+                }
+                KeyCode::Char(c)
+            }
+        };
+
+        // A caixa do caractere já indica o shift.
+        if let KeyCode::Char(_) = code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Some(KeyBinding { code, modifiers })
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        let mut modifiers = key.modifiers;
+        if let KeyCode::Char(_) = key.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        if let KeyCode::BackTab = key.code {
+            return self.code == KeyCode::Tab && self.modifiers == KeyModifiers::SHIFT;
+        }
+        self.code == key.code && self.modifiers == modifiers
+    }
+
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Char(' ') => "espaço".to_string(),
+            KeyCode::Backspace => "backspace".to_string(),
+            KeyCode::Delete => "delete".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Home => "home".to_string(),
+            KeyCode::End => "end".to_string(),
+            KeyCode::PageUp => "pgup".to_string(),
+            KeyCode::PageDown => "pgdn".to_string(),
+            KeyCode::F(n) => format!("f{}", n),
+            KeyCode::Char(c) => c.to_string(),
+            _ => "?".to_string(),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("ctrl-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("alt-");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            label.push_str("shift-");
+        }
+        label + &key
+    }
+}
+
+
+/// Teclas ativas de cada ação, montadas a partir dos padrões e da seção `[keys]`.
+pub struct KeyMap {
+    bindings: Vec<(Action, Vec<KeyBinding>)>,
+    /// Ações que continuam com tecla mas não aparecem no rodapé, como as bloqueadas no modo
+    /// somente leitura.
+    hidden: Vec<Action>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap::new(&BTreeMap::new()).0
+    }
+}
+
+impl KeyMap {
+    /// Monta o mapa de teclas, aplicando por cima dos padrões o que estiver no config.toml.
+    ///
+    /// Retorna também a lista de problemas encontrados: ações ou teclas desconhecidas e teclas
+    /// associadas a mais de uma ação no mesmo contexto. Em um conflito, a tecla configurada em
+    /// `[keys]` vence a padrão; entre duas configuradas (ou duas padrão) vale a primeira ação. Uma
+    /// ação cujas teclas configuradas são todas inválidas volta às teclas padrão.
+    pub fn new(keys: &BTreeMap<String, KeySpec>) -> (KeyMap, Vec<String>) {
+        let mut problems = Vec::new();
+
+        for name in keys.keys() {
+            if Action::from_name(name).is_none() {
+                problems.push(format!("ação desconhecida em [keys]: {}", name));
+            }
+        }
+
+        // Teclas configuradas; `None` para as ações que ficam com as padrão.
+        let configured: Vec<Option<Vec<KeyBinding>>> = Action::ALL
+            .iter()
+            .map(|action| {
+                let spec = keys.get(action.name())?;
+                let parsed: Vec<KeyBinding> = spec
+                    .keys()
+                    .into_iter()
+                    .filter_map(|key| {
+                        let binding = KeyBinding::parse(key);
+                        if binding.is_none() {
+                            problems.push(format!("tecla inválida para {}: {}", action.name(), key));
+                        }
+                        binding
+                    })
+                    .collect();
+                (!parsed.is_empty()).then_some(parsed)
+            })
+            .collect();
+
+        let mut bindings: Vec<(Action, Vec<KeyBinding>)> = Action::ALL.iter().map(|action| (*action, Vec::new())).collect();
+        let explicit = configured.iter().enumerate().filter_map(|(i, keys)| Some((i, keys.clone()?)));
+        let defaults = configured.iter().enumerate().filter(|(_, keys)| keys.is_none()).map(|(i, _)| {
+            let keys = Action::ALL[i].default_keys().iter().filter_map(|key| KeyBinding::parse(key)).collect();
+            (i, keys)
+        });
+        for (i, keys) in explicit.chain(defaults).collect::<Vec<(usize, Vec<KeyBinding>)>>() {
+            let action = Action::ALL[i];
+            for binding in keys {
+                let conflict = bindings
+                    .iter()
+                    .find(|(other, keys)| other.context() == action.context() && keys.contains(&binding));
+                match conflict {
+                    Some((other, _)) => problems.push(format!(
+                        "tecla {} usada em {} e {} ({})",
+                        binding.label(),
+                        other.name(),
+                        action.name(),
+                        action.context().label(),
+                    )),
+                    None => bindings[i].1.push(binding),
+                }
+            }
+        }
+
+        (KeyMap { bindings, hidden: Vec::new() }, problems)
+    }
+
+    pub fn hide(&mut self, actions: &[Action]) {
+        self.hidden.extend_from_slice(actions);
+    }
+
+    /// Ação associada à tecla pressionada no contexto informado.
+    pub fn action(&self, key: &KeyEvent, context: Context) -> Option<Action> {
+        self.bindings
+            .iter()
+            .filter(|(action, _)| action.context() == context)
+            .find(|(_, keys)| keys.iter().any(|k| k.matches(key)))
+            .map(|(action, _)| *action)
+    }
+
+    /// Teclas da ação separadas por `/`, ou vazio se a ação ficou sem tecla.
+    pub fn keys_label(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| keys.iter().map(|k| k.label()).collect::<Vec<_>>().join("/"))
+            .unwrap_or_default()
+    }
+
+    /// Texto do rodapé com as ações da tabela, no formato `Descrição <tecla>`.
+    ///
+    /// Ajuda e comandos vêm primeiro; as demais ações entram enquanto couberem em `max_len`
+    /// caracteres, para o rodapé não sumir em terminais estreitos.
+    pub fn footer_text(&self, max_len: usize) -> String {
+        let first = [Action::Help, Action::Palette, Action::Quit];
+        let rest = Action::ALL
+            .into_iter()
+            .filter(|a| {
+                a.context() == Context::Table
+                    && !first.contains(a)
+                    && !self.hidden.contains(a)
+                    && !matches!(a, Action::Up | Action::Down | Action::ExtendUp | Action::ExtendDown)
+            });
+
+        let mut text = String::new();
+        for action in first.into_iter().chain(rest) {
+            let keys = self.keys_label(action);
+            if keys.is_empty() {
+                continue;
+            }
+            let item = format!("{} <{}>", action.description(), keys);
+            let len = text.chars().count() + item.chars().count() + 2;
+            if !text.is_empty() && len > max_len {
+                break;
+            }
+            if !text.is_empty() {
+                text.push_str("  ");
+            }
+            text.push_str(&item);
+        }
+        text
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(pairs: &[(&str, &str)]) -> BTreeMap<String, KeySpec> {
+        pairs.iter().map(|(action, key)| (action.to_string(), KeySpec::One(key.to_string()))).collect()
+    }
+
+    fn press(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let (keymap, problems) = KeyMap::new(&BTreeMap::new());
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(keymap.action(&press('d'), Context::Table), Some(Action::Delete));
+        assert_eq!(keymap.action(&press('s'), Context::Confirm), Some(Action::Confirm));
+    }
+
+    #[test]
+    fn configured_key_wins_over_a_default() {
+        let (keymap, problems) = KeyMap::new(&keys(&[("delete", "x")]));
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("delete") && problems[0].contains("export"), "{}", problems[0]);
+        assert_eq!(keymap.action(&press('x'), Context::Table), Some(Action::Delete));
+        assert_eq!(keymap.keys_label(Action::Export), "");
+
+        // Também quando a ação configurada vem depois da padrão em `Action::ALL`.
+        let (keymap, problems) = KeyMap::new(&keys(&[("export", "d")]));
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert_eq!(keymap.action(&press('d'), Context::Table), Some(Action::Export));
+        assert_eq!(keymap.keys_label(Action::Delete), "");
+    }
+
+    #[test]
+    fn conflict_between_configured_keys_stays_with_the_first_action() {
+        let (keymap, problems) = KeyMap::new(&keys(&[("delete", "z"), ("export", "z")]));
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert_eq!(keymap.action(&press('z'), Context::Table), Some(Action::Delete));
+        // Sem a tecla configurada, `export` não volta à padrão: ela foi trocada de propósito.
+        assert_eq!(keymap.action(&press('x'), Context::Table), None);
+    }
+
+    #[test]
+    fn same_key_in_another_context_is_not_a_conflict() {
+        let (keymap, problems) = KeyMap::new(&keys(&[("delete", "s")]));
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(keymap.action(&press('s'), Context::Table), Some(Action::Delete));
+        assert_eq!(keymap.action(&press('s'), Context::Confirm), Some(Action::Confirm));
+    }
+
+    #[test]
+    fn reports_unknown_actions_and_invalid_keys() {
+        let (keymap, problems) = KeyMap::new(&keys(&[("apagar", "z"), ("delete", "ctrl-")]));
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert!(problems.iter().any(|p| p.contains("apagar")));
+        assert!(problems.iter().any(|p| p.contains("ctrl-")));
+        // Sem nenhuma tecla válida, a ação volta à padrão.
+        assert_eq!(keymap.action(&press('d'), Context::Table), Some(Action::Delete));
+    }
+
+    #[test]
+    fn keeps_the_valid_configured_keys() {
+        let mut config = BTreeMap::new();
+        config.insert("delete".to_string(), KeySpec::Many(vec!["ctrl-".to_string(), "z".to_string()]));
+        let (keymap, problems) = KeyMap::new(&config);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert_eq!(keymap.action(&press('z'), Context::Table), Some(Action::Delete));
+        assert_eq!(keymap.action(&press('d'), Context::Table), None);
+    }
+
+    #[test]
+    fn many_keys_for_one_action() {
+        let mut config = BTreeMap::new();
+        config.insert("down".to_string(), KeySpec::Many(vec!["down".to_string(), "j".to_string()]));
+        let (keymap, problems) = KeyMap::new(&config);
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!(keymap.action(&press('j'), Context::Table), Some(Action::Down));
+        assert_eq!(keymap.action(&KeyEvent::new(KeyCode::Down, KeyModifiers::NONE), Context::Table), Some(Action::Down));
+    }
+}
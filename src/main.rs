@@ -1,8 +1,8 @@
 use api_service::Entry;
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use crossterm::{event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture}, execute, terminal::{disable_raw_mode, enable_raw_mode}};
 use errors::TerminalError;
 use ratatui::{DefaultTerminal, Frame};
-use std::time::Duration;
+use std::{io::stdout, time::Duration};
 use tokio::{sync::mpsc, time::interval};
 
 mod cli_monitor;
@@ -10,11 +10,77 @@ mod api_service;
 mod modal;
 mod config;
 mod errors;
+mod audit;
+mod actions;
+mod args;
+mod history;
+mod trends;
+mod changes;
+mod columns;
+mod keymap;
+mod palette;
+mod theme;
+mod detail;
+mod grouping;
+mod duplicates;
+mod protection;
+mod message;
+mod text_input;
+mod http;
+mod exporter;
+mod serve;
+mod hooks;
+mod alerts;
+mod export;
 
 #[tokio::main]
 async fn main() {
+    let command = match args::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    let read_only = matches!(command, args::Command::Monitor { read_only: true });
+    let result = match command {
+        args::Command::Monitor { .. } => None,
+        args::Command::Audit(query) => Some(
+            config::load_config()
+                .map_err(TerminalError::from)
+                .and_then(|config| audit::print_records(&config, &query).map_err(TerminalError::from))
+        ),
+        args::Command::History(query) => Some(
+            config::load_config()
+                .map_err(TerminalError::from)
+                .and_then(|config| history::print_query(&config, &query).map_err(TerminalError::from))
+        ),
+        args::Command::Exporter { listen } => Some(match config::load_config() {
+            Ok(config) => exporter::run(config, listen).await,
+            Err(e) => Err(TerminalError::from(e)),
+        }),
+        args::Command::Hooks { event } => Some(match config::load_config() {
+            Ok(config) => hooks::test(&config, event).await.map_err(TerminalError::from),
+            Err(e) => Err(TerminalError::from(e)),
+        }),
+        args::Command::Serve { listen, read_only } => Some(match config::load_config() {
+            Ok(mut config) => {
+                config.forced_read_only = read_only;
+                serve::run(config, listen).await
+            }
+            Err(e) => Err(TerminalError::from(e)),
+        }),
+    };
+    if let Some(result) = result {
+        if let Err(e) = result {
+            println!("{}", e);
+        }
+        return;
+    }
+
     let mut terminal = ratatui::init();
-    let result = run(&mut terminal);
+    let result = run(&mut terminal, read_only);
     
     let mut has_error : bool = false;
     let mut error_message : String = String::new();
@@ -23,6 +89,7 @@ async fn main() {
         Err(e) => {
             has_error = true;
             error_message = e.to_string();
+            let _ = execute!(stdout(), DisableMouseCapture, DisableBracketedPaste);
             disable_raw_mode().unwrap();
         },
     }
@@ -41,25 +108,55 @@ async fn main() {
 ///
 /// If there is an error drawing the terminal, this function will return an error.
 ///
-async fn run(terminal: &mut DefaultTerminal) -> Result<(), TerminalError> {
-    modal::draw_loading(terminal)?;
+async fn run(terminal: &mut DefaultTerminal, read_only: bool) -> Result<(), TerminalError> {
+    modal::draw_loading(terminal, &theme::Theme::initial())?;
 
-    let mut monitor = cli_monitor::CliMonitor::new();
     let mut page = 0;
 
-    let config = config::load_config()?;
+    let mut config = config::load_config()?;
+    config.forced_read_only = read_only;
+    let mut monitor = cli_monitor::CliMonitor::new(&config.columns);
+    match theme::Theme::load(&config.theme, &config.themes) {
+        Ok(theme) => monitor.theme = theme,
+        Err(e) => {
+            monitor.error = cli_monitor::MonitorError::ThemeError(e);
+            monitor.is_on_error = true;
+        }
+    }
+    let (keymap, problems) = keymap::KeyMap::new(&config.keys);
+    monitor.keymap = keymap;
+    monitor.protected = config.protected.clone();
+    monitor.blocked = [audit::AuditAction::Delete, audit::AuditAction::Message]
+        .into_iter()
+        .filter(|action| config.blocks(*action))
+        .collect();
+    if config.blocks(audit::AuditAction::Delete) {
+        monitor.keymap.hide(&[keymap::Action::Delete, keymap::Action::KillDuplicates]);
+    }
+    if config.blocks(audit::AuditAction::Message) {
+        monitor.keymap.hide(&[keymap::Action::Message]);
+    }
+    if !problems.is_empty() {
+        monitor.error = cli_monitor::MonitorError::KeyMapError(problems.join("\n"));
+        monitor.is_on_error = true;
+    }
 
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(config.request_timeout_in_secs))
         .build()?;
 
     let mut token: String =  api_service::get_token(&config,&client).await?;
-    let mut entries: Vec<Entry> = api_service::get_entries(&config,&token, &client, page, 10).await?;
+    let mut entries: Vec<Entry> = api_service::get_entries(&config,&token, &client, page, config.page_size).await?;
+    if let Err(e) = cli_monitor::refresh_listing(&mut monitor, &config, &token, &client).await {
+        monitor.error = e;
+        monitor.is_on_error = true;
+    }
 
     let (tx, mut rx) = mpsc::unbounded_channel::<TimerEvent>();
-    let mut input_buffer = String::new();
+    let mut composer = message::Composer::new(&config.templates, &config.message_history_file);
 
     enable_raw_mode()?;
+    execute!(stdout(), EnableMouseCapture, EnableBracketedPaste)?;
     create_timer(&tx, TimerEvent::Refresh ,Duration::from_secs(config.refresh_interval_in_secs));
     create_timer(&tx, TimerEvent::Every30Min ,Duration::from_secs(30 * 3600));
     terminal.clear()?;
@@ -72,7 +169,7 @@ async fn run(terminal: &mut DefaultTerminal) -> Result<(), TerminalError> {
         if ctrl_c.is_finished() {
             break;
         }
-        terminal.draw(|f| draw(f, &mut monitor, &entries, &mut input_buffer))?;
+        terminal.draw(|f| draw(f, &mut monitor, &mut entries, &composer, &config))?;
         
         let has_exited = cli_monitor::user_key_input(
             &mut monitor, 
@@ -80,8 +177,8 @@ async fn run(terminal: &mut DefaultTerminal) -> Result<(), TerminalError> {
             &mut page, 
             &token, 
             &client, 
-            &config, 
-            &mut input_buffer
+            &mut config, 
+            &mut composer
         ).await;
         
         match has_exited {
@@ -97,23 +194,31 @@ async fn run(terminal: &mut DefaultTerminal) -> Result<(), TerminalError> {
         };
         
         
-        if let Ok(Some(_)) = rx.try_recv().map(Some) {
-            cli_monitor::update(&config,&token, &client, page, &mut entries).await;
-        }
-
-        if let Ok(Some(event)) = rx.try_recv().map(Some) {
+        while let Ok(event) = rx.try_recv() {
             match event {
                 TimerEvent::Refresh => {
                     cli_monitor::update(&config,&token, &client, page, &mut entries).await;
+
+                    if let Err(e) = cli_monitor::refresh_listing(&mut monitor, &config, &token, &client).await {
+                        monitor.error = e;
+                        monitor.is_on_error = true;
+                    }
                 },
                 TimerEvent::Every30Min => {
                     token = api_service::get_token(&config,&client).await?;
                 }
             }
         }
+
+        let failures = hooks::take_failures();
+        if !failures.is_empty() && !monitor.is_on_error {
+            monitor.error = cli_monitor::MonitorError::HookError(failures.join("\n"));
+            monitor.is_on_error = true;
+        }
         
     }
 
+    execute!(stdout(), DisableMouseCapture, DisableBracketedPaste)?;
     disable_raw_mode()?;
     terminal.clear()?;
     ratatui::restore();
@@ -133,32 +238,99 @@ async fn run(terminal: &mut DefaultTerminal) -> Result<(), TerminalError> {
 /// * `f` - The frame to render the interface.
 /// * `monitor` - The CLI monitor state, which tracks the current modal and error state.
 /// * `entries` - A vector of entries representing the data to be displayed.
-/// * `input_buffer` - A buffer containing the user's input for the message modal.
-fn draw(f: &mut Frame, monitor: &mut cli_monitor::CliMonitor, entries: &Vec<Entry>, input_buffer: &mut String) {
-    if let Err(e) = cli_monitor::render(&monitor, entries,f) {
+/// * `composer` - The message being typed in the message modal, with its templates and history.
+/// * `config` - The configuration, for the delete confirmation settings.
+fn draw(f: &mut Frame, monitor: &mut cli_monitor::CliMonitor, entries: &mut Vec<Entry>, composer: &message::Composer, config: &config::Config) {
+    monitor.changes.expire();
+    monitor.apply_filter(entries);
+    monitor.apply_sort(entries);
+    if let Err(e) = cli_monitor::render(monitor, entries,f) {
         println!("Error: {}", e);
     }
 
     match &monitor.error {
         cli_monitor::MonitorError::None => {}
         cli_monitor::MonitorError::SendMsgError(msg) => {
-            modal::draw_error(f, "Erro ao enviar mensagem", &msg);
+            modal::draw_error(f, &monitor.theme, "Erro ao enviar mensagem", msg);
+        }
+        cli_monitor::MonitorError::DeleteError(msg) => {
+            modal::draw_error(f, &monitor.theme, "Erro ao desconectar", msg);
+        }
+        cli_monitor::MonitorError::AuditError(msg) => {
+            modal::draw_error(f, &monitor.theme, "Erro no log de auditoria", msg);
+        }
+        cli_monitor::MonitorError::HistoryError(msg) => {
+            modal::draw_error(f, &monitor.theme, "Erro ao gravar histórico", msg);
+        }
+        cli_monitor::MonitorError::ConfigError(msg) => {
+            modal::draw_error(f, &monitor.theme, "Erro ao salvar configuração", msg);
+        }
+        cli_monitor::MonitorError::ThemeError(msg) => {
+            modal::draw_error(f, &monitor.theme, "Tema inválido, usando o padrão", msg);
+        }
+        cli_monitor::MonitorError::KeyMapError(msg) => {
+            modal::draw_error(f, &monitor.theme, "Problemas na seção [keys] do config.toml", msg);
+        }
+        cli_monitor::MonitorError::ReadOnly(msg) => {
+            modal::draw_error(f, &monitor.theme, "Modo somente leitura", msg);
+        }
+        cli_monitor::MonitorError::HookError(msg) => {
+            modal::draw_error(f, &monitor.theme, "Erro ao disparar ganchos", msg);
+        }
+        cli_monitor::MonitorError::ExportError(msg) => {
+            modal::draw_error(f, &monitor.theme, "Erro ao exportar", msg);
         }
     }
     
     if monitor.on_modal {
+        // A tela de detalhes fica por baixo dos modais abertos a partir dela.
+        if let Some(detail) = &monitor.detail {
+            detail::draw_detail(f, &monitor.theme, detail, &monitor.keymap);
+        }
         match monitor.current_modal {
             cli_monitor::Modal::Delete =>{
-                modal::draw_confirm_del_modal(f);
+                let mut targets = cli_monitor::selected_targets(monitor, entries);
+                cli_monitor::sort_targets(&mut targets);
+                let offscreen = monitor.offscreen(&targets, entries);
+                let typed = modal::confirmation_word(config, &targets).map(|word| (word, &monitor.confirm_input));
+                modal::draw_confirm_del_modal(f, &monitor.theme, &monitor.keymap, &targets, offscreen, typed, &config.protected);
             }
             cli_monitor::Modal::Info => {
-                
-                let entry: &Entry = &entries[monitor.selected as usize];
-                modal::draw_more_info_modal(f, entry);
+                if let Some(entry) = monitor.selected_entry(entries) {
+                    modal::draw_more_info_modal(f, &monitor.theme, entry);
+                }
             }
             cli_monitor::Modal::SendMsg => {
-                let entry: &Entry = &entries[monitor.selected as usize];
-                modal::draw_send_message_modal(f,&entry, input_buffer);
+                let targets = cli_monitor::selected_targets(monitor, entries);
+                if !targets.is_empty() {
+                    modal::draw_send_message_modal(f, &monitor.theme, &targets, composer);
+                }
+            }
+            cli_monitor::Modal::Context => {
+                modal::draw_context_menu(f, &monitor.theme, &monitor.context_menu);
+            }
+            cli_monitor::Modal::Columns => {
+                modal::draw_column_picker(f, &monitor.theme, &monitor.column_picker);
+            }
+            cli_monitor::Modal::Help => {
+                modal::draw_help_modal(f, &monitor.theme, &monitor.keymap, monitor.help_scroll);
+            }
+            cli_monitor::Modal::Palette => {
+                modal::draw_palette(f, &monitor.theme, &monitor.palette, &monitor.keymap);
+            }
+            cli_monitor::Modal::Detail | cli_monitor::Modal::Filter => {}
+            cli_monitor::Modal::Review => {
+                let targets = cli_monitor::marked_targets(monitor, entries);
+                modal::draw_review_modal(f, &monitor.theme, &targets, monitor.review_cursor, &monitor.keymap);
+            }
+            cli_monitor::Modal::Audit => {
+                modal::draw_audit_modal(f, &monitor.theme, &monitor.audit_records, monitor.audit_scroll);
+            }
+            cli_monitor::Modal::Alerts => {
+                modal::draw_alerts_modal(f, &monitor.theme, &monitor.alerts);
+            }
+            cli_monitor::Modal::Export => {
+                modal::draw_export_modal(f, &monitor.theme, &monitor.export, entries.len(), monitor.all_entries.len());
             }
             cli_monitor::Modal::None => {}
         }
@@ -0,0 +1,128 @@
+use std::{collections::BTreeMap, fs, io};
+
+use crate::{audit::AuditTarget, text_input::TextInput};
+
+/// Quantidade de mensagens guardadas no histórico.
+const HISTORY_LIMIT: usize = 100;
+
+/// Marcadores aceitos nos modelos e no texto digitado.
+pub const PLACEHOLDERS: [&str; 5] = ["{user}", "{program}", "{machine}", "{time}", "{minutes}"];
+
+
+/// Troca os marcadores pelos dados de um destinatário.
+///
+/// `{user}`, `{program}` e `{machine}` são o usuário, o programa e o computador da conexão,
+/// `{time}` o tempo de conexão e `{minutes}` os minutos de inatividade.
+pub fn fill(text: &str, target: &AuditTarget) -> String {
+    let minutes = target.inactive_secs().map(|s| (s / 60).to_string()).unwrap_or_default();
+    text.replace("{user}", &target.user_name)
+        .replace("{program}", &target.function)
+        .replace("{machine}", &target.machine_name)
+        .replace("{time}", &target.time_up)
+        .replace("{minutes}", &minutes)
+}
+
+pub fn has_placeholders(text: &str) -> bool {
+    PLACEHOLDERS.iter().any(|p| text.contains(p))
+}
+
+
+/// Estado do modal de mensagem: o texto digitado, os modelos de `[templates]` e o histórico de
+/// mensagens enviadas, navegado com ↑ ↓ como no shell.
+#[derive(Default)]
+pub struct Composer {
+    pub input: TextInput,
+    /// Modelos por nome, na ordem do config.toml.
+    pub templates: Vec<(String, String)>,
+    /// Modelo escolhido por último com tab.
+    pub template: Option<usize>,
+    /// Mensagens enviadas, da mais antiga para a mais recente.
+    history: Vec<String>,
+    /// Posição no histórico enquanto ele é navegado; `None` é o texto em edição.
+    history_pos: Option<usize>,
+    /// Texto em edição antes de entrar no histórico, devolvido ao sair dele por baixo.
+    draft: String,
+}
+
+impl Composer {
+    pub fn new(templates: &BTreeMap<String, String>, history_file: &str) -> Self {
+        Self {
+            templates: templates.iter().map(|(name, text)| (name.clone(), text.clone())).collect(),
+            history: load_history(history_file),
+            ..Default::default()
+        }
+    }
+
+    /// Limpa o texto e volta ao fim do histórico, para a próxima mensagem.
+    pub fn reset(&mut self) {
+        self.input.clear();
+        self.template = None;
+        self.history_pos = None;
+        self.draft.clear();
+    }
+
+    /// Troca o texto pelo próximo modelo (ou pelo anterior, com `forward` falso).
+    pub fn cycle_template(&mut self, forward: bool) {
+        let len = self.templates.len();
+        if len == 0 {
+            return;
+        }
+        let next = match (self.template, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+        };
+        self.template = Some(next);
+        self.input.set(self.templates[next].1.clone());
+    }
+
+    /// Mensagem anterior do histórico.
+    pub fn history_back(&mut self) {
+        let pos = match self.history_pos {
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.input.text().to_string();
+                self.history.len() - 1
+            }
+            Some(pos) => pos.saturating_sub(1),
+        };
+        self.history_pos = Some(pos);
+        self.input.set(self.history[pos].clone());
+    }
+
+    /// Mensagem seguinte do histórico; depois da mais recente volta ao texto em edição.
+    pub fn history_forward(&mut self) {
+        let Some(pos) = self.history_pos else { return };
+        if pos + 1 < self.history.len() {
+            self.history_pos = Some(pos + 1);
+            self.input.set(self.history[pos + 1].clone());
+        } else {
+            self.history_pos = None;
+            self.input.set(std::mem::take(&mut self.draft));
+        }
+    }
+
+    /// Guarda a mensagem enviada no fim do histórico e grava o arquivo.
+    ///
+    /// Uma mensagem igual à última não é repetida.
+    pub fn remember(&mut self, history_file: &str) -> io::Result<()> {
+        let text = self.input.text().trim();
+        if text.is_empty() || self.history.last().is_some_and(|last| last == text) {
+            return Ok(());
+        }
+        self.history.push(text.to_string());
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.drain(..self.history.len() - HISTORY_LIMIT);
+        }
+        fs::write(history_file, self.history.join("\n") + "\n")
+    }
+}
+
+
+/// Lê o histórico de mensagens, uma por linha. Um arquivo ausente é um histórico vazio.
+fn load_history(path: &str) -> Vec<String> {
+    fs::read_to_string(path)
+        .map(|content| content.lines().filter(|l| !l.trim().is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
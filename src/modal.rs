@@ -6,7 +6,7 @@ use ratatui::{
 };
 use reqwest::Client;
 
-use crate::{api_service, cli_monitor::MonitorError, config::Config, errors::TerminalError};
+use crate::{actions, alerts::Alerts, api_service, export::{Export, ExportFormat}, audit::{AuditRecord, AuditTarget}, cli_monitor::{Modal, MonitorError}, columns::ColumnPicker, config::Config, errors::TerminalError, keymap::{Action, Context, KeyMap}, message::{self, Composer}, palette::Palette, protection::{self, ProtectedRule}, text_input::TextInput, theme::Theme};
 
 
 
@@ -14,69 +14,189 @@ use crate::{api_service, cli_monitor::MonitorError, config::Config, errors::Term
 /// Handles key events for the delete confirmation modal.
 ///
 /// This function processes key inputs to confirm or cancel the deletion
-/// of connections. If the 's' key is pressed, the function triggers the
-/// deletion of connections using the provided entry IDs and returns false,
-/// indicating the modal should close. If the 'n' key is pressed, it cancels
+/// of connections. If the `confirm` key is pressed ('s' by default), the function triggers the
+/// deletion of the given connections, recording it in the audit log, and returns false,
+/// indicating the modal should close. If the `cancel` key ('n' by default) is pressed, it cancels
 /// the deletion and also returns false to close the modal. Any other key
 /// keeps the modal open.
 ///
+/// When there are more targets than `delete_confirmation.type_word_above`, the confirmation word
+/// has to be typed into `typed` and confirmed with Enter instead; Esc cancels. If any target is
+/// protected, `delete_confirmation.override_word` has to be typed, and typing it is the explicit
+/// override that lets protected connections be disconnected.
+///
 /// # Arguments
 ///
 /// * `key` - The key event to process.
-/// * `entries_id` - A vector of entry IDs to be deleted if confirmed.
+/// * `keymap` - The active key bindings.
+/// * `targets` - Snapshots of the connections to be deleted if confirmed.
+/// * `typed` - What has been typed so far of the confirmation word. Cleared when the modal closes.
 /// * `token` - The authorization token for the API request.
 /// * `client` - The HTTP client used to make the API request.
 /// * `config` - The configuration used for the API request.
 ///
 /// # Returns
 ///
-/// Returns `false` if the modal should close, and `true` if it should remain open.
+/// Returns `Ok(false)` if the modal should close, `Ok(true)` if it should remain open,
+/// and `Err(MonitorError)` if the deletion or the audit record failed.
 pub async fn confirm_del_modal(
     key : &event::KeyEvent, 
-    entries_id : &Vec<String>, 
+    keymap : &KeyMap,
+    targets : &[AuditTarget], 
+    typed : &mut TextInput,
     token: &str, 
     client: &Client,
     config : &Config
-)-> bool{
-    match key.code {
-        KeyCode::Char('s') => {
-            api_service::delete_connections(config,entries_id, &token, &client).await;
-            return false;
+)-> Result<bool, MonitorError>{
+    let protected = targets.iter().any(|t| protection::is_protected(&config.protected, t));
+    if let Some(word) = confirmation_word(config, targets) {
+        match key.code {
+            KeyCode::Enter if typed.text().trim() == word => {}
+            KeyCode::Esc => {
+                typed.clear();
+                return Ok(false);
+            }
+            _ => {
+                typed.handle(key);
+                return Ok(true);
+            }
         }
-        KeyCode::Char('n') => {
-            return false;
-            
-        }
-        _ => {
-            return true;
+    } else {
+        match keymap.action(key, Context::Confirm) {
+            Some(Action::Confirm) => {}
+            Some(Action::Cancel) => return Ok(false),
+            _ => return Ok(true),
         }
     }
+
+    typed.clear();
+    match actions::disconnect(config, targets, protected, None, token, client).await {
+        Ok(_) => Ok(false),
+        Err(actions::ActionError::Audit(e)) => Err(MonitorError::AuditError(e.to_string())),
+        Err(e) => Err(MonitorError::DeleteError(e.to_string())),
+    }
+}
+
+/// Palavra que precisa ser digitada para desconectar `targets`, ou `None` quando basta a tecla de
+/// confirmar.
+pub fn confirmation_word<'a>(config: &'a Config, targets: &[AuditTarget]) -> Option<&'a str> {
+    let confirmation = &config.delete_confirmation;
+    if targets.iter().any(|t| protection::is_protected(&config.protected, t)) {
+        Some(&confirmation.override_word)
+    } else if targets.len() > confirmation.type_word_above {
+        Some(&confirmation.word)
+    } else {
+        None
+    }
 }
 
 /// Renderiza um modal de confirma o para desconectar conex es do Protheus.
+///
+/// Lista cada conexão que será desconectada. Acima do limite de `[delete_confirmation]`, pede
+/// a palavra de confirmação no lugar das teclas de confirmar e cancelar.
 /// 
 /// # Argumentos
 /// 
 /// * `f` - frame que ser  renderizado.
+/// * `theme` - tema da sessão.
+/// * `keymap` - teclas ativas, para exibir as teclas de confirmação.
+/// * `targets` - conexões que serão desconectadas.
+/// * `offscreen` - quantas delas não estão na tela.
+/// * `typed` - palavra exigida e o que já foi digitado, quando a confirmação é digitada.
+/// * `rules` - regras de `[[protected]]`, para marcar as conexões protegidas.
 /// 
 /// # Retorno
 /// 
 /// Nenhum retorno.
-pub fn draw_confirm_del_modal(f: &mut Frame){
-    let area = centered_rect(30, 10, f.area());
+pub fn draw_confirm_del_modal(
+    f: &mut Frame, theme: &Theme,
+    keymap: &KeyMap,
+    targets: &[AuditTarget],
+    offscreen: usize,
+    typed: Option<(&str, &TextInput)>,
+    rules: &[ProtectedRule],
+){
+    let area = centered_rect(70, 60, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
     let block = Block::default()
-        .title("Confirmar desconexão?")
+        .title(match targets.len() {
+            1 => "Confirmar desconexão?".to_string(),
+            n => format!("Desconectar {} conexões?", n),
+        })
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::White).bg(Color::Red));
+        .style(theme.danger());
 
-    let paragraph = Paragraph::new("Tem certeza que deseja desconectar? (s/n)")
-        .style(Style::default().fg(Color::White).bg(Color::Red))
-        .block(block)
-        .wrap(Wrap { trim: true });
+    let row = |user: &str, machine: &str, function: &str, environment: &str, idle: &str| {
+        format!("   {:<18} {:<16} {:<18} {:<12} {}", user, machine, function, environment, idle)
+    };
+    let protected = targets.iter().filter(|t| protection::is_protected(rules, t)).count();
+    // Linhas de baixo: espaço, aviso de fora da tela, pergunta e campo digitado.
+    let room = area.height.saturating_sub(2 + 5) as usize;
+    let mut lines = vec![Line::styled(
+        row("usuário", "computador", "programa", "ambiente", "inativo"),
+        Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+    )];
+    let shown = if targets.len() > room { room.saturating_sub(1) } else { targets.len() };
+    for target in &targets[..shown] {
+        let text = if target.user_name.is_empty() {
+            format!("   {}", target.describe())
+        } else {
+            row(&target.user_name, &target.machine_name, &target.function, &target.environment, &target.inactive_time)
+        };
+        lines.push(if protection::is_protected(rules, target) {
+            Line::styled(format!("{}{}", protection::LOCK, &text[2..]), Style::default().add_modifier(Modifier::BOLD))
+        } else {
+            Line::from(text)
+        });
+    }
+    if shown < targets.len() {
+        lines.push(Line::from(format!("... e mais {}", targets.len() - shown)));
+    }
+    lines.push(Line::from(""));
+    if offscreen > 0 {
+        lines.push(Line::from(format!("{} destas conexões não estão na tela.", offscreen)));
+    }
+    match typed {
+        Some((word, typed)) if protected > 0 => {
+            lines.push(Line::from(format!(
+                "{} {} protegida{}. Digite {} e <enter> para desconectar mesmo assim, <esc> cancela:",
+                protection::LOCK,
+                protected,
+                if protected == 1 { "" } else { "s" },
+                word,
+            )));
+            lines.push(typed_line(typed));
+        }
+        Some((word, typed)) => {
+            lines.push(Line::from(format!(
+                "São {} conexões. Digite {} e <enter> para confirmar, <esc> cancela:",
+                targets.len(),
+                word,
+            )));
+            lines.push(typed_line(typed));
+        }
+        None => lines.push(Line::from(format!(
+            "Tem certeza que deseja desconectar? ({}/{})",
+            keymap.keys_label(Action::Confirm),
+            keymap.keys_label(Action::Cancel),
+        ))),
+    }
+
+    let paragraph = Paragraph::new(Text::from(lines))
+        .style(theme.danger())
+        .block(block);
 
     f.render_widget(paragraph, area);
 }
 
+/// Linha com a palavra de confirmação sendo digitada.
+fn typed_line(typed: &TextInput) -> Line<'static> {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut spans = vec![Span::styled("> ", bold)];
+    spans.extend(typed.spans(bold));
+    Line::from(spans)
+}
+
 
 /// Processa teclas pressionadas enquanto o modal de mais informa es est  aberto.
 /// 
@@ -88,15 +208,7 @@ pub fn draw_confirm_del_modal(f: &mut Frame){
 /// 
 /// Retorna true se o modal de mais informa es deve permanecer aberto. Caso contr rio, retorna false.
 pub async fn more_info_keys(key : &event::KeyEvent)-> bool{
-    
-    match key.code {
-        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => {
-            return false;
-        }
-        _ => {
-            return true;
-        }
-    }
+    !matches!(key.code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter)
 }
 
 
@@ -105,13 +217,14 @@ pub async fn more_info_keys(key : &event::KeyEvent)-> bool{
 /// # Argumentos
 /// 
 /// * `f` - frame que ser  renderizado.
+/// * `theme` - tema da sessão.
 /// * `entry` - estrutura que cont m as informa es sobre a conex o.
-pub fn draw_more_info_modal(f: &mut Frame, entry: &api_service::Entry) {
+pub fn draw_more_info_modal(f: &mut Frame, theme: &Theme, entry: &api_service::Entry) {
     let area = centered_rect(40, 30, f.area());
     let block = Block::default()
-        .title("Informações")
+        .title("Informações - <enter> acompanha em tela cheia")
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::White).bg(Color::Black));
+        .style(theme.modal());
 
     let label_width = 15;
     let value_width = 27;
@@ -168,15 +281,19 @@ pub fn draw_more_info_modal(f: &mut Frame, entry: &api_service::Entry) {
 /// Handles key events for the message sending modal.
 ///
 /// This function processes key inputs to control the message sending modal.
-/// Characters are appended to the input buffer, and backspace removes the last character.
+/// Editing keys (characters, cursor movement, Home/End, Backspace/Delete, Ctrl-W, Ctrl-U) go to
+/// the text input; any other key is ignored and keeps the modal open.
+/// Tab and Shift+Tab replace the text with the next or previous template, and Up/Down walk
+/// through the messages sent before.
 /// The Enter key sends the message using the provided entry IDs and returns a result indicating success or failure.
+/// A message sent successfully is added to the message history.
 /// The Esc key clears the input buffer and closes the modal.
 ///
 /// # Arguments
 ///
 /// * `key` - The key event to process.
-/// * `input_buffer` - The buffer containing the message to be sent.
-/// * `entries` - A vector of entry IDs to send the message to.
+/// * `composer` - The message being typed, the templates and the message history.
+/// * `entries` - Snapshots of the connections to send the message to.
 /// * `token` - The authorization token for the API request.
 /// * `client` - The HTTP client used to make the API request.
 /// * `config` - The configuration used for the API request.
@@ -187,51 +304,66 @@ pub fn draw_more_info_modal(f: &mut Frame, entry: &api_service::Entry) {
 /// and `Err(MonitorError::SendMsgError)` if there is an error sending the message.
 pub async fn message_keys(
     key : &event::KeyEvent, 
-    input_buffer : &mut String, 
-    entries: &Vec<String>, 
+    composer : &mut Composer, 
+    entries: &[AuditTarget], 
     token: &str, 
     client: &Client,
     config : &Config
 ) -> Result<bool, MonitorError> {
     
     match key.code {
-        KeyCode::Char(c) => {
-            input_buffer.push(c);
+        KeyCode::Tab => {
+            composer.cycle_template(true);
             Ok(true)
-        },
-        KeyCode::Backspace => { 
-            input_buffer.pop(); 
+        }
+        KeyCode::BackTab => {
+            composer.cycle_template(false);
             Ok(true)
-        },
+        }
+        KeyCode::Up => {
+            composer.history_back();
+            Ok(true)
+        }
+        KeyCode::Down => {
+            composer.history_forward();
+            Ok(true)
+        }
         KeyCode::Enter => {
-            let resp = api_service::send_messages(config,&entries, &input_buffer, token, &client).await;
-            
-            input_buffer.clear();
-
-            match resp {
-                Ok(resp_msg) => {
-                    let msg = match resp_msg.message{
-                        Some(msg) => msg,
-                        None => "".to_string(),
-                    };
-        
-        
-                    if msg == ""{
-                        Ok(false)
-                    }else{
-                        Err(MonitorError::SendMsgError(msg))
-                    }
-
-                }
+            // Mensagem vazia não é enviada; o modal continua aberto.
+            if composer.input.text().trim().is_empty() {
+                return Ok(true);
+            }
+            let resp = actions::send_message(config, entries, composer.input.text(), None, token, client).await;
+            // Se ao menos um lote foi entregue, o texto vai para o histórico e é limpo, mesmo que
+            // outros lotes tenham falhado ou o histórico não possa ser gravado.
+            let outcome = match resp {
+                Ok(outcome) => outcome,
+                Err(actions::ActionError::Audit(e)) => return Err(MonitorError::AuditError(e.to_string())),
                 Err(e) => return Err(MonitorError::SendMsgError(e.to_string())),
+            };
+            let remembered = if outcome.sent > 0 {
+                composer.remember(&config.message_history_file)
+            } else {
+                Ok(())
+            };
+            composer.reset();
+
+            if !outcome.failed.is_empty() {
+                Err(MonitorError::SendMsgError(outcome.to_string()))
+            } else if let Err(e) = remembered {
+                Err(MonitorError::HistoryError(format!("mensagem enviada, mas o histórico não foi gravado: {}", e)))
+            } else {
+                Ok(false)
             }
-            
         },
         KeyCode::Esc => {
-            input_buffer.clear();
+            composer.reset();
             Ok(false)
         },
-        _ => Ok(false)
+        _ => {
+            composer.input.handle(key);
+            Ok(true)
+        }
     }
 
 }
@@ -240,37 +372,411 @@ pub async fn message_keys(
 
 /// Renderiza um modal para enviar uma mensagem para o usu rio.
 ///
+/// Abaixo do texto mostra como ele fica para o primeiro destinatário, quando tem marcadores,
+/// e os modelos de `[templates]`.
+///
 /// # Argumentos
 ///
 /// * `f` - frame que ser  renderizado.
-/// * `entry` - estrutura que cont m as informa es sobre a conex o.
-/// * `input_buffer` - buffer de entrada com o texto da mensagem que o usu rio est  digitando.
+/// * `theme` - tema da sessão.
+/// * `targets` - conexões que vão receber a mensagem.
+/// * `composer` - texto digitado, modelos e histórico.
 pub fn draw_send_message_modal(
-    f: &mut Frame,
-    entry: &api_service::Entry,
-    input_buffer: &str,
+    f: &mut Frame, theme: &Theme,
+    targets: &[AuditTarget],
+    composer: &Composer,
 ) {
-    let area = centered_rect(60, 20, f.area());
+    let area = centered_rect(60, 40, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
 
+    let title = match targets {
+        [target] => format!("Mensagem para {}", target.describe()),
+        targets => format!("Mensagem para {} conexões", targets.len()),
+    };
     let block = Block::default()
-        .title(format!("Mensagem para {}", entry.user_name))
+        .title(title)
+        .title_bottom("<tab> modelos  ↑ ↓ histórico  <enter> envia  <esc> cancela")
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::White).bg(Color::Black));
+        .style(theme.modal());
+
+    let mut lines = vec![Line::from(composer.input.spans(Style::default())), Line::from("")];
+
+    if let Some(first) = targets.first()
+        && message::has_placeholders(composer.input.text())
+    {
+        lines.push(Line::from(vec![
+            Span::styled(format!("Prévia ({}): ", first.user_name), theme.muted()),
+            Span::raw(message::fill(composer.input.text(), first)),
+        ]));
+        lines.push(Line::from(""));
+    }
 
-    let mut text = input_buffer.to_string();
-    text.push('_');
+    if composer.templates.is_empty() {
+        lines.push(Line::styled(
+            format!("Sem modelos; crie em [templates] no config.toml. Marcadores: {}", message::PLACEHOLDERS.join(" ")),
+            theme.muted(),
+        ));
+    }
+    for (i, (name, template)) in composer.templates.iter().enumerate() {
+        let style = if composer.template == Some(i) { theme.highlight() } else { Style::default() };
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {:<14}", name), style.add_modifier(Modifier::BOLD)),
+            Span::styled(format!(" {}", template), theme.muted()),
+        ]));
+    }
 
-    let paragraph = Paragraph::new(Text::from(Line::from(text)))
+    let paragraph = Paragraph::new(Text::from(lines))
         .block(block)
         .alignment(Alignment::Left)
         .wrap(Wrap { trim: false })
-        .style(Style::default().fg(Color::White).bg(Color::Black));
+        .style(theme.modal());
 
     f.render_widget(paragraph, area);
 }
     
 
 
+/// Processa teclas pressionadas enquanto o log de auditoria está aberto.
+///
+/// # Argumentos
+///
+/// * `key` - tecla pressionada.
+/// * `scroll` - primeiro registro visível.
+/// * `len` - quantidade de registros carregados.
+///
+/// # Retorno
+///
+/// Retorna true se o modal deve permanecer aberto.
+pub fn audit_keys(key : &event::KeyEvent, scroll: &mut usize, len: usize) -> bool {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => false,
+        KeyCode::Down => {
+            if *scroll + 1 < len {
+                *scroll += 1;
+            }
+            true
+        }
+        KeyCode::Up => {
+            *scroll = scroll.saturating_sub(1);
+            true
+        }
+        KeyCode::PageDown => {
+            *scroll = (*scroll + 10).min(len.saturating_sub(1));
+            true
+        }
+        KeyCode::PageUp => {
+            *scroll = scroll.saturating_sub(10);
+            true
+        }
+        _ => true,
+    }
+}
+
+
+/// Renderiza o log de auditoria, do registro mais recente para o mais antigo.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `records` - registros carregados do arquivo de auditoria.
+/// * `scroll` - primeiro registro visível.
+pub fn draw_audit_modal(f: &mut Frame, theme: &Theme, records: &[AuditRecord], scroll: usize) {
+    let area = centered_rect(80, 70, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let block = Block::default()
+        .title(format!("Auditoria ({} registros) - ↑ ↓ PgUp PgDn, <q> fecha", records.len()))
+        .borders(Borders::ALL)
+        .style(theme.modal());
+
+    let mut lines: Vec<Line> = Vec::new();
+    if records.is_empty() {
+        lines.push(Line::from("Nenhum registro de auditoria."));
+    }
+    for record in records.iter().skip(scroll) {
+        lines.push(Line::from(vec![
+            Span::styled(record.local_time(), theme.accent()),
+            Span::raw(format!(" {} ", record.requester())),
+            Span::styled(record.action.label(), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!(" {} ({})", record.server, record.environment)),
+        ]));
+        for target in &record.targets {
+            lines.push(Line::from(format!("    - {}", target.describe())));
+        }
+        if let Some(message) = &record.message {
+            lines.push(Line::from(format!("    mensagem: {}", message)));
+        }
+        if !record.response.is_empty() {
+            lines.push(Line::from(format!("    resposta: {}", record.response)));
+        }
+    }
+
+    let paragraph = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(paragraph, area);
+}
+
+
+/// Renderiza o histórico de alertas da sessão, do mais recente para o mais antigo.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `alerts` - alertas ativos e encerrados, com o primeiro visível em `scroll`.
+pub fn draw_alerts_modal(f: &mut Frame, theme: &Theme, alerts: &Alerts) {
+    let area = centered_rect(80, 70, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let block = Block::default()
+        .title(format!("Alertas ({} ativos) - ↑ ↓ PgUp PgDn, <q> fecha", alerts.active().count()))
+        .borders(Borders::ALL)
+        .style(theme.modal());
+
+    let mut lines: Vec<Line> = Vec::new();
+    if alerts.history.is_empty() {
+        lines.push(Line::from("Nenhum alerta nesta sessão. As regras ficam em [[alerts]] no config.toml."));
+    }
+    for record in alerts.history.iter().skip(alerts.scroll) {
+        let (state, style) = match record.ended {
+            None => ("ATIVO".to_string(), theme.danger()),
+            Some(ended) => (format!("até {}", ended.format("%H:%M:%S")), theme.muted()),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(record.started.format("%d/%m/%Y %H:%M:%S").to_string(), theme.accent()),
+            Span::raw(" "),
+            Span::styled(state, style),
+            Span::styled(format!(" {}", record.label), Style::default().add_modifier(Modifier::BOLD)),
+        ]));
+        lines.push(Line::from(format!("    {}", record.detail)));
+    }
+
+    let paragraph = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(paragraph, area);
+}
+
+
+/// Renderiza o modal de exportação, com o escopo escolhido e o último arquivo gravado.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `export` - escopo e último arquivo gravado.
+/// * `view` - quantidade de conexões na visão atual da tabela.
+/// * `full` - quantidade de conexões na listagem completa.
+pub fn draw_export_modal(f: &mut Frame, theme: &Theme, export: &Export, view: usize, full: usize) {
+    let area = centered_rect(50, 30, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let block = Block::default()
+        .title("Exportar")
+        .title_bottom("<tab> troca o escopo  <esc> fecha")
+        .borders(Borders::ALL)
+        .style(theme.modal());
+
+    let scope = |selected: bool, label: String| {
+        let style = if selected { theme.selection() } else { Style::default() };
+        Span::styled(label, style)
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            Span::raw("Escopo: "),
+            scope(!export.full, format!(" visão atual ({}) ", view)),
+            Span::raw(" "),
+            scope(export.full, format!(" listagem completa ({}) ", full)),
+        ]),
+        Line::from(""),
+    ];
+    let mut formats = vec![Span::raw("Formato: ")];
+    for format in ExportFormat::ALL {
+        formats.push(Span::styled(format!("<{}>", format.key()), theme.accent()));
+        formats.push(Span::raw(format!(" {}  ", format.label())));
+    }
+    lines.push(Line::from(formats));
+    if let Some(path) = &export.last {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(format!("Gravado em {}", path.display()), theme.added_text())));
+    }
+
+    let paragraph = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(paragraph, area);
+}
+
+
+
+pub enum PickerAction {
+    Stay,
+    Cancel,
+    Save,
+}
+
+/// Processa teclas pressionadas no modal de colunas.
+///
+/// ↑ ↓ movem o cursor, espaço mostra/esconde a coluna, Shift+↑ ↓ (ou `K`/`J`) mudam a ordem,
+/// `+`/`-` ajustam a largura, Enter salva no config.toml e Esc descarta as alterações.
+pub fn column_picker_keys(key : &event::KeyEvent, picker: &mut ColumnPicker) -> PickerAction {
+    let shift = key.modifiers.contains(event::KeyModifiers::SHIFT);
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => return PickerAction::Cancel,
+        KeyCode::Enter => return PickerAction::Save,
+        KeyCode::Up if shift => picker.reorder(false),
+        KeyCode::Down if shift => picker.reorder(true),
+        KeyCode::Char('K') => picker.reorder(false),
+        KeyCode::Char('J') => picker.reorder(true),
+        KeyCode::Up => picker.move_cursor(false),
+        KeyCode::Down => picker.move_cursor(true),
+        KeyCode::Char(' ') => picker.toggle(),
+        KeyCode::Char('+') => picker.resize(2),
+        KeyCode::Char('-') => picker.resize(-2),
+        _ => {}
+    }
+    PickerAction::Stay
+}
+
+
+/// Renderiza o modal de escolha de colunas.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `picker` - estado do modal com as colunas sendo editadas.
+pub fn draw_column_picker(f: &mut Frame, theme: &Theme, picker: &ColumnPicker) {
+    let area = centered_rect(40, 60, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let total: u16 = picker.items.iter().filter(|i| i.2).map(|i| i.1).sum();
+    let block = Block::default()
+        .title(format!("Colunas (largura total {}%)", total))
+        .title_bottom("<espaço> mostra  <K/J> ordem  <+/-> largura  <enter> salva  <esc> cancela")
+        .borders(Borders::ALL)
+        .style(theme.modal());
+
+    let lines: Vec<Line> = picker.items
+        .iter()
+        .enumerate()
+        .map(|(i, (field, width, visible))| {
+            let text = format!(
+                "[{}] {:<20} {:>3}%",
+                if *visible { "x" } else { " " },
+                field.label(),
+                width,
+            );
+            let mut style = Style::default();
+            if !visible {
+                style = style.patch(theme.muted());
+            }
+            if i == picker.cursor {
+                style = style.patch(theme.highlight());
+            }
+            Line::styled(text, style)
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+
+
+/// Menu aberto com o botão direito sobre uma linha da tabela.
+#[derive(Default)]
+pub struct ContextMenu {
+    pub x: u16,
+    pub y: u16,
+    pub cursor: usize,
+}
+
+impl ContextMenu {
+    const ITEMS: [&'static str; 3] = ["Desconectar", "Enviar mensagem", "Mais detalhes"];
+
+    pub fn at(x: u16, y: u16) -> Self {
+        Self { x, y, cursor: 0 }
+    }
+
+    fn modal(index: usize) -> Modal {
+        match index {
+            0 => Modal::Delete,
+            1 => Modal::SendMsg,
+            _ => Modal::Info,
+        }
+    }
+
+    /// Área do menu, deslocada para caber dentro do terminal.
+    fn area(&self, frame: Rect) -> Rect {
+        let width = 20;
+        let height = Self::ITEMS.len() as u16 + 2;
+        let x = self.x.min(frame.right().saturating_sub(width));
+        let y = self.y.min(frame.bottom().saturating_sub(height));
+        Rect::new(x, y, width, height).intersection(frame)
+    }
+
+    /// Modal correspondente à opção sob a posição clicada, se houver.
+    pub fn item_at(&self, x: u16, y: u16, frame: Rect) -> Option<Modal> {
+        let area = self.area(frame);
+        if x <= area.x || x >= area.right().saturating_sub(1) || y <= area.y || y >= area.bottom().saturating_sub(1) {
+            return None;
+        }
+        Some(Self::modal((y - area.y - 1) as usize))
+    }
+}
+
+/// Processa teclas pressionadas no menu de contexto.
+///
+/// # Retorno
+///
+/// `None` mantém o menu aberto, `Some(None)` fecha e `Some(Some(modal))` abre o modal escolhido.
+pub fn context_menu_keys(key : &event::KeyEvent, menu: &mut ContextMenu) -> Option<Option<Modal>> {
+    let len = ContextMenu::ITEMS.len();
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') => Some(None),
+        KeyCode::Enter => Some(Some(ContextMenu::modal(menu.cursor))),
+        KeyCode::Down => {
+            menu.cursor = (menu.cursor + 1) % len;
+            None
+        }
+        KeyCode::Up => {
+            menu.cursor = (menu.cursor + len - 1) % len;
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Renderiza o menu de contexto na posição em que foi aberto.
+pub fn draw_context_menu(f: &mut Frame, theme: &Theme, menu: &ContextMenu) {
+    let area = menu.area(f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let lines: Vec<Line> = ContextMenu::ITEMS
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            if i == menu.cursor {
+                Line::styled(*item, theme.highlight())
+            } else {
+                Line::from(*item)
+            }
+        })
+        .collect();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .style(theme.modal());
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+
+
 /// Calculate the position of a centered rect with the given percentage of the area.
 ///
 /// The given rect is split into three parts, and the middle part is split again
@@ -312,28 +818,29 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 /// # Argumentos
 ///
 /// * `f` - frame que ser  renderizado.
+/// * `theme` - tema da sessão.
 /// * `tittle` - t tulo do modal.
 /// * `message` - mensagem a ser exibida no modal.
-pub fn draw_error(f: &mut Frame,tittle: &str, message: &str) {
+pub fn draw_error(f: &mut Frame, theme: &Theme,tittle: &str, message: &str) {
 
     let area = centered_rect(30, 10, f.area());
     let block = Block::default()
         .title(tittle)
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::White).bg(Color::Red));
+        .style(theme.danger());
 
     let paragraph = Paragraph::new(Text::from(message))
         .block(block)
         .alignment(Alignment::Left)
         .wrap(Wrap { trim: false })
-        .style(Style::default().fg(Color::White).bg(Color::Red));
+        .style(theme.danger());
 
     f.render_widget(paragraph, area);
 }
 
 
 
-pub fn draw_loading(terminal: &mut DefaultTerminal) -> Result<(), TerminalError>{
+pub fn draw_loading(terminal: &mut DefaultTerminal, theme: &Theme) -> Result<(), TerminalError>{
     terminal.draw(|f| {
         let size = f.area();
 
@@ -342,7 +849,7 @@ pub fn draw_loading(terminal: &mut DefaultTerminal) -> Result<(), TerminalError>
             .borders(Borders::ALL);
         
         let paragraph = Paragraph::new(Line::from(vec![
-            Span::styled("Carregando...", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
+            Span::styled("Carregando...", theme.accent().add_modifier(Modifier::BOLD)),
         ]))
         .alignment(Alignment::Center)
         .block(block);
@@ -350,4 +857,212 @@ pub fn draw_loading(terminal: &mut DefaultTerminal) -> Result<(), TerminalError>
         f.render_widget(paragraph, size);
     })?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Linhas da ajuda, agrupadas por contexto, com as teclas ativas de cada ação.
+///
+/// As teclas fixas dos modais e do mouse não estão no mapa de teclas e são listadas à parte.
+pub fn help_lines(keymap: &KeyMap, theme: &Theme) -> Vec<Line<'static>> {
+    let title = |text: &str| Line::styled(text.to_string(), theme.accent().add_modifier(Modifier::BOLD));
+    let item = |description: &str, keys: String, name: &str| {
+        Line::from(vec![
+            Span::raw(format!("  {:<22}", description)),
+            Span::styled(keys, Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(if name.is_empty() { String::new() } else { format!("  ({})", name) }, theme.muted()),
+        ])
+    };
+
+    let mut lines: Vec<Line> = Vec::new();
+    for group in ["Tabela", "Navegação", "Seleção", "Modal de desconexão"] {
+        lines.push(title(group));
+        for action in Action::ALL.iter().filter(|a| a.group() == group) {
+            let keys = match keymap.keys_label(*action) {
+                keys if keys.is_empty() => "-".to_string(),
+                keys => keys,
+            };
+            lines.push(item(action.description(), keys, action.name()));
+        }
+        if group == "Navegação" {
+            lines.push(item("Rola uma tela", "pgup/pgdn".to_string(), ""));
+            lines.push(item("Início / fim", "home/end".to_string(), ""));
+        }
+        lines.push(Line::from(""));
+    }
+
+    let fixed = [
+        ("Modais", vec![
+            ("Fecha", "esc/q/enter"),
+            ("Rola", "↑/↓/pgup/pgdn"),
+        ]),
+        ("Colunas", vec![
+            ("Mostra/esconde", "espaço"),
+            ("Muda a ordem", "shift-↑/↓ ou K/J"),
+            ("Largura", "+/-"),
+            ("Salva / descarta", "enter/esc"),
+        ]),
+        ("Campos de texto", vec![
+            ("Move o cursor", "←/→ ctrl-←/→ home/end"),
+            ("Apaga palavra / início", "ctrl-w/ctrl-u"),
+            ("Filtro: mantém / limpa", "enter/esc"),
+        ]),
+        ("Comandos", vec![
+            ("Busca", "digite parte do nome"),
+            ("Escolhe / executa", "↑/↓ e enter"),
+        ]),
+        ("Mouse", vec![
+            ("Seleciona / detalhes", "clique / duplo clique"),
+            ("Ordena pela coluna", "clique no cabeçalho"),
+            ("Menu de contexto", "botão direito"),
+        ]),
+        ("Marcadores", vec![
+            ("Mesmo programa 2x+", "◆"),
+            ("Vários computadores", "◇"),
+        ]),
+    ];
+    for (group, items) in fixed {
+        lines.push(title(group));
+        for (description, keys) in items {
+            lines.push(item(description, keys.to_string(), ""));
+        }
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from("Para trocar uma tecla, use a seção [keys] do config.toml, ex.: delete = \"x\"."));
+    lines
+}
+
+
+/// Renderiza a ajuda com as teclas ativas de cada ação.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `keymap` - teclas ativas.
+/// * `scroll` - primeira linha exibida.
+pub fn draw_help_modal(f: &mut Frame, theme: &Theme, keymap: &KeyMap, scroll: usize) {
+    let area = centered_rect(60, 80, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let block = Block::default()
+        .title("Ajuda - ↑ ↓ PgUp PgDn, <q> fecha")
+        .borders(Borders::ALL)
+        .style(theme.modal());
+
+    let lines: Vec<Line> = help_lines(keymap, theme).into_iter().skip(scroll).collect();
+    let paragraph = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(paragraph, area);
+}
+
+
+/// Processa teclas pressionadas na paleta de comandos.
+///
+/// # Retorno
+///
+/// `None` mantém a paleta aberta, `Some(None)` fecha e `Some(Some(action))` executa a ação escolhida.
+pub fn palette_keys(key : &event::KeyEvent, palette: &mut Palette) -> Option<Option<Action>> {
+    match key.code {
+        KeyCode::Esc => Some(None),
+        KeyCode::Enter => Some(palette.selected()),
+        KeyCode::Down => {
+            palette.move_cursor(true);
+            None
+        }
+        KeyCode::Up => {
+            palette.move_cursor(false);
+            None
+        }
+        KeyCode::Backspace => {
+            palette.pop();
+            None
+        }
+        KeyCode::Char(c) => {
+            palette.push(c);
+            None
+        }
+        _ => None,
+    }
+}
+
+
+/// Renderiza a paleta de comandos com as ações que casam com a busca.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `palette` - estado da paleta.
+/// * `keymap` - teclas ativas, exibidas ao lado de cada ação.
+pub fn draw_palette(f: &mut Frame, theme: &Theme, palette: &Palette, keymap: &KeyMap) {
+    let area = centered_rect(50, 50, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let block = Block::default()
+        .title("Comandos - <enter> executa, <esc> fecha")
+        .borders(Borders::ALL)
+        .style(theme.modal());
+
+    let mut lines = vec![
+        Line::from(vec![Span::styled(": ", theme.accent()), Span::raw(palette.query.clone())]),
+        Line::from(""),
+    ];
+    let matches = palette.matches();
+    if matches.is_empty() {
+        lines.push(Line::styled("Nenhum comando encontrado.", theme.muted()));
+    }
+    for (i, action) in matches.iter().enumerate() {
+        let style = if i == palette.cursor {
+            theme.highlight()
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {:<32}", action.title().unwrap_or_default()), style),
+            Span::styled(format!(" {}", keymap.keys_label(*action)), theme.muted()),
+        ]));
+    }
+
+    f.render_widget(Paragraph::new(Text::from(lines)).block(block), area);
+}
+
+
+/// Renderiza a revisão das conexões marcadas, antes de uma ação em lote.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `targets` - conexões marcadas.
+/// * `cursor` - linha destacada.
+/// * `keymap` - teclas ativas, exibidas no título.
+pub fn draw_review_modal(f: &mut Frame, theme: &Theme, targets: &[AuditTarget], cursor: usize, keymap: &KeyMap) {
+    let area = centered_rect(70, 60, f.area());
+    f.render_widget(ratatui::widgets::Clear, area);
+
+    let block = Block::default()
+        .title(format!(
+            "Selecionadas ({}) - <espaço> desmarca, <{}> desconecta, <{}> mensagem, <esc> fecha",
+            targets.len(),
+            keymap.keys_label(Action::Delete),
+            keymap.keys_label(Action::Message),
+        ))
+        .borders(Borders::ALL)
+        .style(theme.modal());
+
+    // Mantém o cursor visível rolando a lista.
+    let height = area.height.saturating_sub(2) as usize;
+    let skip = (cursor + 1).saturating_sub(height);
+    let lines: Vec<Line> = targets.iter().enumerate().skip(skip).map(|(i, target)| {
+        let style = if i == cursor { theme.highlight() } else { Style::default() };
+        let text = if target.user_name.is_empty() {
+            format!(" {}", target.describe())
+        } else {
+            format!(" {:<18} {:<16} {:<18} {}", target.user_name, target.machine_name, target.function, target.environment)
+        };
+        Line::styled(text, style)
+    }).collect();
+
+    f.render_widget(Paragraph::new(Text::from(lines)).block(block), area);
+}
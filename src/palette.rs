@@ -0,0 +1,91 @@
+use crate::keymap::{Action, Context};
+
+
+/// Estado da paleta de comandos aberta com `:`.
+#[derive(Default)]
+pub struct Palette {
+    pub query: String,
+    pub cursor: usize,
+}
+
+impl Palette {
+    /// Ações da tabela que casam com o texto digitado, da mais para a menos relevante.
+    ///
+    /// Sem texto, lista todas na ordem em que aparecem na ajuda.
+    pub fn matches(&self) -> Vec<Action> {
+        let mut scored: Vec<(i32, Action)> = Action::ALL
+            .into_iter()
+            .filter(|a| a.context() == Context::Table && a.title().is_some())
+            .filter_map(|a| {
+                let title = a.title()?;
+                let score = fuzzy_score(&self.query, title).max(fuzzy_score(&self.query, a.name()).map(|s| s - 1))?;
+                Some((score, a))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, a)| a).collect()
+    }
+
+    pub fn selected(&self) -> Option<Action> {
+        self.matches().get(self.cursor).copied()
+    }
+
+    pub fn move_cursor(&mut self, down: bool) {
+        let len = self.matches().len();
+        if len == 0 {
+            return;
+        }
+        self.cursor = if down { (self.cursor + 1) % len } else { (self.cursor + len - 1) % len };
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.cursor = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.cursor = 0;
+    }
+}
+
+
+/// Pontua `text` pela busca aproximada de `query`: todas as letras da busca precisam aparecer
+/// no texto, na mesma ordem, mas não necessariamente juntas.
+///
+/// Letras seguidas e no início de palavras valem mais. Ignora maiúsculas e acentos.
+/// Retorna `None` quando o texto não casa.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().map(fold).collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for q in query.chars().filter(|c| !c.is_whitespace()).map(fold) {
+        let found = (position..text.len()).find(|&i| text[i] == q)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 3;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+
+/// Minúscula sem acento, para comparar "graficos" com "Gráficos".
+fn fold(c: char) -> char {
+    match c.to_lowercase().next().unwrap_or(c) {
+        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        c => c,
+    }
+}
@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{api_service::Entry, audit::AuditTarget};
+
+/// Marcador das conexões protegidas na tabela e na confirmação de desconexão.
+pub const LOCK: &str = "🔒";
+
+
+/// Regra de `[[protected]]` no config.toml.
+///
+/// Cada campo é um padrão com `*` e `?`, sem diferenciar maiúsculas. A conexão é protegida
+/// quando casa com todos os campos informados da regra; uma regra sem campos não protege nada.
+///
+/// ```toml
+/// [[protected]]
+/// user_name = "JOB*"
+///
+/// [[protected]]
+/// function = "RPC"
+/// thread_type = "REST"
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ProtectedRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub machine_name: Option<String>,
+}
+
+impl ProtectedRule {
+    fn matches(&self, user_name: &str, function: &str, thread_type: &str, machine_name: &str) -> bool {
+        let fields = [
+            (&self.user_name, user_name),
+            (&self.function, function),
+            (&self.thread_type, thread_type),
+            (&self.machine_name, machine_name),
+        ];
+        fields.iter().any(|(pattern, _)| pattern.is_some())
+            && fields
+                .iter()
+                .all(|(pattern, value)| pattern.as_deref().is_none_or(|p| glob_match(p, value.trim())))
+    }
+}
+
+
+pub fn is_protected(rules: &[ProtectedRule], target: &AuditTarget) -> bool {
+    rules
+        .iter()
+        .any(|r| r.matches(&target.user_name, &target.function, &target.thread_type, &target.machine_name))
+}
+
+pub fn is_protected_entry(rules: &[ProtectedRule], entry: &Entry) -> bool {
+    rules
+        .iter()
+        .any(|r| r.matches(&entry.user_name, &entry.function, &entry.thread_type, &entry.machine_name))
+}
+
+
+/// Compara `value` com um padrão em que `*` casa com qualquer sequência e `?` com um caractere.
+fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let value: Vec<char> = value.to_lowercase().chars().collect();
+
+    let (mut p, mut v) = (0, 0);
+    // Posição do último `*` no padrão e até onde ele já consumiu o valor.
+    let mut star: Option<(usize, usize)> = None;
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, v));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match star {
+                Some((star_p, star_v)) => {
+                    p = star_p + 1;
+                    v = star_v + 1;
+                    star = Some((star_p, star_v + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(user_name: &str, function: &str, thread_type: &str, machine_name: &str) -> AuditTarget {
+        AuditTarget {
+            id: "1".to_string(),
+            user_name: user_name.to_string(),
+            machine_name: machine_name.to_string(),
+            function: function.to_string(),
+            environment: "P12".to_string(),
+            thread_type: thread_type.to_string(),
+            time_up: String::new(),
+            inactive_time: String::new(),
+        }
+    }
+
+    #[test]
+    fn glob_match_literal_ignores_case() {
+        assert!(glob_match("job01", "JOB01"));
+        assert!(!glob_match("JOB01", "JOB011"));
+        assert!(!glob_match("JOB01", "JOB0"));
+    }
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(glob_match("JOB*", "JOB"));
+        assert!(glob_match("JOB*", "JOB01"));
+        assert!(glob_match("*01", "JOB01"));
+        assert!(glob_match("J*B*1", "JOB01"));
+        assert!(glob_match("JOB0?", "JOB01"));
+        assert!(!glob_match("JOB0?", "JOB0"));
+        assert!(!glob_match("JOB*", "ADMIN"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("?", ""));
+    }
+
+    #[test]
+    fn glob_match_backtracks_after_a_partial_match() {
+        assert!(glob_match("*ab", "aab"));
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(!glob_match("*ab", "aba"));
+    }
+
+    #[test]
+    fn rule_needs_every_given_field() {
+        let rule = ProtectedRule {
+            function: Some("RPC".to_string()),
+            thread_type: Some("REST".to_string()),
+            ..Default::default()
+        };
+        let rules = [rule];
+        assert!(is_protected(&rules, &target("ANA", "RPC", "REST", "PC01")));
+        assert!(!is_protected(&rules, &target("ANA", "RPC", "SmartClient", "PC01")));
+        assert!(!is_protected(&rules, &target("ANA", "MATA010", "REST", "PC01")));
+    }
+
+    #[test]
+    fn rule_trims_padded_fields() {
+        let rules = [ProtectedRule { user_name: Some("JOB*".to_string()), ..Default::default() }];
+        assert!(is_protected(&rules, &target("JOB01   ", "", "", "")));
+    }
+
+    #[test]
+    fn empty_rule_protects_nothing() {
+        let rules = [ProtectedRule::default()];
+        assert!(!is_protected(&rules, &target("ANA", "RPC", "REST", "PC01")));
+        assert!(!is_protected(&[], &target("ANA", "RPC", "REST", "PC01")));
+    }
+
+    #[test]
+    fn any_rule_protects() {
+        let rules = [
+            ProtectedRule { user_name: Some("JOB*".to_string()), ..Default::default() },
+            ProtectedRule { machine_name: Some("SRV-*".to_string()), ..Default::default() },
+        ];
+        assert!(is_protected(&rules, &target("JOB01", "", "", "PC01")));
+        assert!(is_protected(&rules, &target("ANA", "", "", "SRV-APP")));
+        assert!(!is_protected(&rules, &target("ANA", "", "", "PC01")));
+    }
+}
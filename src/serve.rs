@@ -0,0 +1,412 @@
+use std::{collections::HashSet, net::SocketAddr, sync::Arc, time::{Duration, Instant}};
+
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::Mutex;
+
+use crate::{
+    actions::{self, ActionError},
+    api_service::{self, Connection, Entry},
+    audit::AuditTarget,
+    config::Config,
+    errors::{APIError, TerminalError},
+    hooks,
+    http::{self, Request, Response},
+};
+
+/// Parâmetro de `GET /connections` e o campo que ele filtra.
+type Filter = (&'static str, fn(&Entry) -> &str);
+
+
+#[derive(Deserialize)]
+struct KillRequest {
+    ids: Vec<String>,
+    /// Desconecta também as conexões protegidas.
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(Deserialize)]
+struct MessageRequest {
+    ids: Vec<String>,
+    message: String,
+}
+
+
+/// Sessão com o Protheus compartilhada pelas requisições, autenticada de novo quando o token
+/// expira ou uma busca falha.
+struct Session {
+    config: Config,
+    client: Client,
+    token: Mutex<Option<(String, Instant)>>,
+    /// Última listagem completa e quando foi buscada, reaproveitada por `refresh_interval_in_secs`.
+    listing: Mutex<Option<(Vec<Entry>, Instant)>>,
+}
+
+impl Session {
+    /// Token válido, autenticando de novo se passou de `TOKEN_TTL` ou se `renew` for verdadeiro.
+    async fn token(&self, renew: bool) -> Result<String, APIError> {
+        let mut token = self.token.lock().await;
+        if let Some((current, since)) = &*token
+            && !renew
+            && since.elapsed() < api_service::TOKEN_TTL
+        {
+            return Ok(current.clone());
+        }
+        let fresh = api_service::get_token(&self.config, &self.client).await?;
+        *token = Some((fresh.clone(), Instant::now()));
+        Ok(fresh)
+    }
+
+    /// Listagem completa, buscada de novo só depois de `refresh_interval_in_secs`, para que
+    /// requisições seguidas não percorram todas as páginas a cada vez. Se a busca falhar,
+    /// autentica de novo e tenta mais uma vez, já que o token pode ter expirado no servidor antes
+    /// do prazo.
+    async fn entries(&self) -> Result<Vec<Entry>, APIError> {
+        // A trava fica presa durante a busca, então requisições simultâneas esperam a mesma busca.
+        let mut listing = self.listing.lock().await;
+        if let Some((entries, since)) = &*listing
+            && since.elapsed() < Duration::from_secs(self.config.refresh_interval_in_secs)
+        {
+            return Ok(entries.clone());
+        }
+        let token = self.token(false).await?;
+        let entries = match api_service::get_all_entries(&self.config, &token, &self.client).await {
+            Ok(entries) => entries,
+            Err(_) => {
+                let token = self.token(true).await?;
+                api_service::get_all_entries(&self.config, &token, &self.client).await?
+            }
+        };
+        *listing = Some((entries.clone(), Instant::now()));
+        Ok(entries)
+    }
+
+    /// Descarta a listagem guardada, depois de uma desconexão.
+    async fn forget_listing(&self) {
+        *self.listing.lock().await = None;
+    }
+}
+
+
+/// Runs `climonitor serve`: a small JSON API over the WebMonitor endpoints.
+///
+/// It keeps the Protheus session, walks every page of the listing at most once per
+/// `refresh_interval_in_secs` and authenticates again when needed. Disconnects and messages go through `actions`, so they honour read-only mode and
+/// `[[protected]]` rules and are written to the audit log with the API client.
+///
+/// `listen` overrides `serve.listen` from the config.
+///
+/// # Errors
+///
+/// Returns a `TerminalError` if the first authentication fails, if the address is not local and
+/// `serve.api_key` is empty, or if the address can't be listened on.
+pub async fn run(config: Config, listen: Option<String>) -> Result<(), TerminalError> {
+    let listen = listen.unwrap_or_else(|| config.serve.listen.clone());
+    if config.serve.api_key.is_empty() && !is_local(&listen) {
+        return Err(TerminalError::ServeError(format!(
+            "defina serve.api_key no config.toml para escutar em {}",
+            listen
+        )));
+    }
+
+    let client = Client::builder()
+        .timeout(Duration::from_secs(config.request_timeout_in_secs))
+        .build()?;
+    let read_only = config.is_read_only();
+    let session = Arc::new(Session { config, client, token: Mutex::new(None), listing: Mutex::new(None) });
+    session.token(true).await?;
+
+    println!(
+        "API em http://{}/connections{}",
+        listen,
+        if read_only { " (somente leitura)" } else { "" }
+    );
+    http::serve(&listen, move |request| {
+        let session = session.clone();
+        async move { route(&session, request).await }
+    })
+    .await?;
+    Ok(())
+}
+
+
+fn is_local(listen: &str) -> bool {
+    match listen.parse::<SocketAddr>() {
+        Ok(addr) => addr.ip().is_loopback(),
+        Err(_) => listen.starts_with("localhost:"),
+    }
+}
+
+
+/// Compares the whole strings whatever the first difference is, so the response time doesn't
+/// tell how much of the key a guess got right.
+fn same_secret(given: &str, expected: &str) -> bool {
+    let (given, expected) = (given.as_bytes(), expected.as_bytes());
+    let diff = expected
+        .iter()
+        .enumerate()
+        .fold(given.len() ^ expected.len(), |diff, (i, b)| diff | usize::from(given.get(i).unwrap_or(&0) ^ b));
+    diff == 0
+}
+
+
+async fn route(session: &Session, request: Request) -> Response {
+    for failure in hooks::take_failures() {
+        eprintln!("{}", failure);
+    }
+    let api_key = &session.config.serve.api_key;
+    let expected = format!("Bearer {}", api_key);
+    if !api_key.is_empty() && !same_secret(request.header("authorization").unwrap_or(""), &expected) {
+        return error(401, "Informe Authorization: Bearer <serve.api_key>");
+    }
+
+    let path = request.path.trim_end_matches('/');
+    match (request.method.as_str(), path) {
+        ("GET", "") => Response::json(200, &json!({
+            "endpoints": [
+                "GET /connections?user=&machine=&program=&environment=&server=&thread_type=",
+                "GET /connections/{id}",
+                "POST /connections/kill {\"ids\": [...], \"force\": false}",
+                "POST /connections/message {\"ids\": [...], \"message\": \"...\"}",
+            ],
+            "read_only": session.config.is_read_only(),
+        })),
+        ("GET", "/connections") => list(session, &request).await,
+        ("POST", "/connections/kill") => kill(session, &request).await,
+        ("POST", "/connections/message") => message(session, &request).await,
+        ("GET", path) if path.starts_with("/connections/") => show(session, &path["/connections/".len()..]).await,
+        (_, "" | "/connections" | "/connections/kill" | "/connections/message") => error(405, "Método não permitido"),
+        _ => error(404, "Não encontrado"),
+    }
+}
+
+
+/// `GET /connections`: the full listing, filtered by the query parameters given.
+///
+/// Filters compare the whole value, ignoring case.
+async fn list(session: &Session, request: &Request) -> Response {
+    let entries = match session.entries().await {
+        Ok(entries) => entries,
+        Err(e) => return error(502, &e.to_string()),
+    };
+
+    let filters: [Filter; 6] = [
+        ("user", |e| &e.user_name),
+        ("machine", |e| &e.machine_name),
+        ("program", |e| &e.function),
+        ("environment", |e| &e.environment),
+        ("server", |e| &e.server),
+        ("thread_type", |e| &e.thread_type),
+    ];
+    let connections: Vec<Connection> = entries
+        .iter()
+        .filter(|entry| {
+            filters.iter().all(|(name, field)| {
+                request.param(name).is_none_or(|wanted| field(entry).trim().eq_ignore_ascii_case(wanted.trim()))
+            })
+        })
+        .map(|entry| Connection::new(entry, &session.config))
+        .collect();
+
+    Response::json(200, &json!({ "count": connections.len(), "connections": connections }))
+}
+
+
+/// `GET /connections/{id}`.
+async fn show(session: &Session, id: &str) -> Response {
+    match session.entries().await {
+        Ok(entries) => match entries.iter().find(|e| e.id == id) {
+            Some(entry) => Response::json(200, &json!(Connection::new(entry, &session.config))),
+            None => error(404, "Conexão não encontrada"),
+        },
+        Err(e) => error(502, &e.to_string()),
+    }
+}
+
+
+/// `POST /connections/kill`: disconnects the given connections after the same checks as the
+/// terminal. Protected connections need `"force": true`.
+async fn kill(session: &Session, request: &Request) -> Response {
+    let body: KillRequest = match serde_json::from_slice(&request.body) {
+        Ok(body) => body,
+        Err(e) => return error(400, &format!("JSON inválido: {}", e)),
+    };
+    let (entries, targets) = match resolve(session, &body.ids).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    let token = match session.token(false).await {
+        Ok(token) => token,
+        Err(e) => return error(502, &e.to_string()),
+    };
+    let api_client = api_client(request);
+    let result = actions::disconnect(&session.config, &targets, body.force, Some(&api_client), &token, &session.client).await;
+    session.forget_listing().await;
+    match result {
+        Ok(response) => Response::json(200, &json!({
+            "disconnected": entries.iter().map(|e| Connection::new(e, &session.config)).collect::<Vec<_>>(),
+            "response": response,
+        })),
+        Err(e) => action_error(e),
+    }
+}
+
+
+/// `POST /connections/message`: sends a message, with placeholders such as `{user}` filled in
+/// for each connection.
+async fn message(session: &Session, request: &Request) -> Response {
+    let body: MessageRequest = match serde_json::from_slice(&request.body) {
+        Ok(body) => body,
+        Err(e) => return error(400, &format!("JSON inválido: {}", e)),
+    };
+    if body.message.trim().is_empty() {
+        return error(400, "Mensagem vazia");
+    }
+    let (_, targets) = match resolve(session, &body.ids).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    let token = match session.token(false).await {
+        Ok(token) => token,
+        Err(e) => return error(502, &e.to_string()),
+    };
+    let api_client = api_client(request);
+    match actions::send_message(&session.config, &targets, &body.message, Some(&api_client), &token, &session.client).await {
+        // Todos os lotes são enviados; os que falharam voltam com o motivo, e a resposta é 502
+        // se algum falhou.
+        Ok(outcome) => {
+            let failed: Vec<_> = outcome.failed.iter().map(|(ids, reason)| json!({ "ids": ids, "error": reason })).collect();
+            let status = if failed.is_empty() { 200 } else { 502 };
+            Response::json(status, &json!({ "sent": outcome.sent, "failed": failed }))
+        }
+        Err(e) => action_error(e),
+    }
+}
+
+
+/// Finds the listed IDs in the current listing, answering 400 for an empty list and 404 with the
+/// missing IDs if any is not connected anymore.
+async fn resolve(session: &Session, ids: &[String]) -> Result<(Vec<Entry>, Vec<AuditTarget>), Response> {
+    if ids.is_empty() {
+        return Err(error(400, "Informe ao menos um id"));
+    }
+    let all = session.entries().await.map_err(|e| error(502, &e.to_string()))?;
+
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    let mut missing = Vec::new();
+    for id in ids.iter().filter(|id| seen.insert(id.as_str())) {
+        match all.iter().find(|e| &e.id == id) {
+            Some(entry) => entries.push(entry.clone()),
+            None => missing.push(id.clone()),
+        }
+    }
+    if !missing.is_empty() {
+        return Err(Response::json(404, &json!({ "error": "Conexões não encontradas", "ids": missing })));
+    }
+
+    let targets = entries.iter().map(AuditTarget::from_entry).collect();
+    Ok((entries, targets))
+}
+
+
+/// Identifies the caller in the audit log: the `X-Client` header, if sent, and the address.
+fn api_client(request: &Request) -> String {
+    match request.header("x-client") {
+        Some(name) if !name.is_empty() => format!("{} {}", name, request.peer),
+        _ => request.peer.clone(),
+    }
+}
+
+
+fn action_error(e: ActionError) -> Response {
+    match e {
+        ActionError::ReadOnly(_) => error(403, &e.to_string()),
+        ActionError::Protected(ref protected) => {
+            Response::json(409, &json!({ "error": e.to_string(), "protected": protected }))
+        }
+        ActionError::NotFound(ref ids) => {
+            Response::json(404, &json!({ "error": "Conexões não encontradas", "ids": ids }))
+        }
+        ActionError::NoTargets => error(400, &e.to_string()),
+        ActionError::Api(_) => error(502, &e.to_string()),
+        ActionError::Audit(_) => error(500, &e.to_string()),
+    }
+}
+
+
+fn error(status: u16, message: &str) -> Response {
+    Response::json(status, &json!({ "error": message }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(extra: &str) -> Session {
+        let base = "login = \"a\"\npassword = \"b\"\nenviorment = \"P12\"\nrefresh_interval_in_secs = 60\n\
+                    ip = \"127.0.0.1\"\nporta = \"1\"\nrequest_timeout_in_secs = 1\n";
+        Session {
+            config: toml::from_str(&format!("{}{}", base, extra)).unwrap(),
+            client: Client::new(),
+            token: Mutex::new(None),
+            listing: Mutex::new(None),
+        }
+    }
+
+    fn request(path: &str, authorization: Option<&str>) -> Request {
+        Request {
+            peer: "127.0.0.1:50000".to_string(),
+            method: "GET".to_string(),
+            path: path.to_string(),
+            query: Vec::new(),
+            headers: authorization.map(|a| ("authorization".to_string(), a.to_string())).into_iter().collect(),
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn compares_the_whole_secret() {
+        assert!(same_secret("Bearer k3y", "Bearer k3y"));
+        assert!(!same_secret("Bearer k3x", "Bearer k3y"));
+        assert!(!same_secret("Bearer k3", "Bearer k3y"));
+        assert!(!same_secret("Bearer k3yy", "Bearer k3y"));
+        assert!(!same_secret("", "Bearer k3y"));
+        assert!(same_secret("", ""));
+    }
+
+    #[tokio::test]
+    async fn refuses_requests_without_the_api_key() {
+        let session = session("[serve]\napi_key = \"k3y\"\n");
+        assert_eq!(route(&session, request("/", None)).await.status, 401);
+        assert_eq!(route(&session, request("/", Some("Bearer k3x"))).await.status, 401);
+        assert_eq!(route(&session, request("/connections", Some("k3y"))).await.status, 401);
+        assert_eq!(route(&session, request("/", Some("Bearer k3y"))).await.status, 200);
+    }
+
+    #[tokio::test]
+    async fn accepts_requests_when_no_api_key_is_set() {
+        let session = session("");
+        assert_eq!(route(&session, request("/", None)).await.status, 200);
+    }
+
+    #[tokio::test]
+    async fn reuses_the_listing_within_the_refresh_interval() {
+        // A porta 1 não responde: qualquer busca no Protheus falharia.
+        let session = session("");
+        let entry = Entry { id: "10".to_string(), user_name: "ANA".to_string(), ..Default::default() };
+        *session.listing.lock().await = Some((vec![entry], Instant::now()));
+
+        let response = route(&session, request("/connections/10", None)).await;
+        assert_eq!(response.status, 200);
+        assert!(response.body.contains("ANA"));
+
+        session.forget_listing().await;
+        assert_eq!(route(&session, request("/connections/10", None)).await.status, 502);
+    }
+}
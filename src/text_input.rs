@@ -0,0 +1,249 @@
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    style::{Modifier, Style},
+    text::Span,
+};
+
+
+/// Campo de texto de uma linha, usado no modal de mensagem, na barra de filtro e no assistente
+/// de configuração.
+///
+/// O cursor conta caracteres, não bytes, então acentos e outros caracteres de mais de um byte
+/// são editados como um só.
+#[derive(Default, Clone, Debug)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+}
+
+impl TextInput {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Troca o texto e põe o cursor no fim.
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text.char_indices().nth(cursor).map(|(i, _)| i).unwrap_or(self.text.len())
+    }
+
+    pub fn insert(&mut self, c: char) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert(index, c);
+        self.cursor += 1;
+    }
+
+    /// Insere um texto colado. Quebras de linha viram espaços, já que o campo tem uma linha só.
+    pub fn insert_str(&mut self, text: &str) {
+        for c in text.replace("\r\n", "\n").chars() {
+            self.insert(if c.is_control() { ' ' } else { c });
+        }
+    }
+
+    /// Apaga os caracteres entre `start` e `end`, em posições de caractere, e leva o cursor para `start`.
+    fn remove(&mut self, start: usize, end: usize) {
+        let (from, to) = (self.byte_index(start), self.byte_index(end));
+        self.text.replace_range(from..to, "");
+        self.cursor = start;
+    }
+
+    /// Início da palavra antes do cursor, pulando os espaços logo antes dele.
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut i = self.cursor;
+        while i > 0 && chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// Fim da palavra depois do cursor.
+    fn word_end(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut i = self.cursor;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    /// Aplica uma tecla de edição.
+    ///
+    /// ← → e Ctrl+← → movem o cursor por caractere e por palavra, Home/End (ou Ctrl-A/Ctrl-E)
+    /// vão ao início e ao fim, Backspace e Delete apagam um caractere, Ctrl-W apaga a palavra antes
+    /// do cursor e Ctrl-U tudo antes dele.
+    ///
+    /// Retorna `false` se a tecla não é de edição, para o chamador tratá-la.
+    pub fn handle(&mut self, key: &KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('w') if ctrl => self.remove(self.word_start(), self.cursor),
+            KeyCode::Char('u') if ctrl => self.remove(0, self.cursor),
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.len(),
+            KeyCode::Char(_) if ctrl || key.modifiers.contains(KeyModifiers::ALT) => return false,
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Backspace if self.cursor > 0 => self.remove(self.cursor - 1, self.cursor),
+            KeyCode::Delete if self.cursor < self.len() => self.remove(self.cursor, self.cursor + 1),
+            KeyCode::Backspace | KeyCode::Delete => {}
+            KeyCode::Left if ctrl => self.cursor = self.word_start(),
+            KeyCode::Right if ctrl => self.cursor = self.word_end(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Texto antes do cursor, o caractere sob o cursor em vídeo reverso (um espaço no fim do
+    /// texto) e o resto, para compor uma `Line`.
+    pub fn spans(&self, style: Style) -> Vec<Span<'static>> {
+        let (before, after) = self.text.split_at(self.byte_index(self.cursor));
+        let mut rest = after.chars();
+        let under = rest.next().map(String::from).unwrap_or_else(|| " ".to_string());
+        vec![
+            Span::styled(before.to_string(), style),
+            Span::styled(under, style.add_modifier(Modifier::REVERSED)),
+            Span::styled(rest.as_str().to_string(), style),
+        ]
+    }
+
+    /// Texto para uma linha de terminal, com cada caractere trocado por `mask` quando houver, e a
+    /// coluna do cursor nele.
+    pub fn display(&self, mask: Option<char>) -> (String, u16) {
+        let before = &self.text[..self.byte_index(self.cursor)];
+        match mask {
+            Some(mask) => (mask.to_string().repeat(self.len()), before.chars().count() as u16),
+            None => (self.text.clone(), Span::raw(before).width() as u16),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(input: &mut TextInput, code: KeyCode) -> bool {
+        input.handle(&KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn ctrl(input: &mut TextInput, code: KeyCode) -> bool {
+        input.handle(&KeyEvent::new(code, KeyModifiers::CONTROL))
+    }
+
+    fn typed(text: &str) -> TextInput {
+        let mut input = TextInput::default();
+        input.insert_str(text);
+        input
+    }
+
+    #[test]
+    fn inserts_at_the_cursor() {
+        let mut input = typed("ac");
+        press(&mut input, KeyCode::Left);
+        press(&mut input, KeyCode::Char('b'));
+        assert_eq!(input.text(), "abc");
+        assert_eq!(input.cursor, 2);
+    }
+
+    #[test]
+    fn edits_multibyte_characters_as_one() {
+        let mut input = typed("ação");
+        press(&mut input, KeyCode::Left);
+        press(&mut input, KeyCode::Backspace);
+        assert_eq!(input.text(), "aço");
+        press(&mut input, KeyCode::Home);
+        press(&mut input, KeyCode::Right);
+        press(&mut input, KeyCode::Delete);
+        assert_eq!(input.text(), "ao");
+        assert_eq!(input.display(None), ("ao".to_string(), 1));
+    }
+
+    #[test]
+    fn backspace_and_delete_stop_at_the_ends() {
+        let mut input = typed("ab");
+        assert!(press(&mut input, KeyCode::Delete));
+        assert_eq!(input.text(), "ab");
+        press(&mut input, KeyCode::Home);
+        assert!(press(&mut input, KeyCode::Backspace));
+        assert_eq!(input.text(), "ab");
+        assert_eq!(input.cursor, 0);
+    }
+
+    #[test]
+    fn ctrl_w_deletes_the_word_before_the_cursor() {
+        let mut input = typed("favor sair  ");
+        ctrl(&mut input, KeyCode::Char('w'));
+        assert_eq!(input.text(), "favor ");
+        ctrl(&mut input, KeyCode::Char('w'));
+        assert_eq!(input.text(), "");
+    }
+
+    #[test]
+    fn ctrl_u_deletes_everything_before_the_cursor() {
+        let mut input = typed("favor sair");
+        ctrl(&mut input, KeyCode::Left);
+        ctrl(&mut input, KeyCode::Char('u'));
+        assert_eq!(input.text(), "sair");
+        assert_eq!(input.cursor, 0);
+    }
+
+    #[test]
+    fn ctrl_arrows_move_by_word() {
+        let mut input = typed("um dois tres");
+        ctrl(&mut input, KeyCode::Left);
+        assert_eq!(input.cursor, 8);
+        ctrl(&mut input, KeyCode::Left);
+        assert_eq!(input.cursor, 3);
+        ctrl(&mut input, KeyCode::Right);
+        assert_eq!(input.cursor, 7);
+    }
+
+    #[test]
+    fn paste_turns_line_breaks_into_spaces() {
+        let input = typed("linha um\r\nlinha dois\tfim");
+        assert_eq!(input.text(), "linha um linha dois fim");
+    }
+
+    #[test]
+    fn leaves_other_keys_to_the_caller() {
+        let mut input = typed("ab");
+        assert!(!press(&mut input, KeyCode::Enter));
+        assert!(!press(&mut input, KeyCode::Esc));
+        assert!(!ctrl(&mut input, KeyCode::Char('c')));
+        assert_eq!(input.text(), "ab");
+    }
+
+    #[test]
+    fn display_masks_every_character() {
+        let mut input = typed("sênha");
+        press(&mut input, KeyCode::Left);
+        assert_eq!(input.display(Some('*')), ("*****".to_string(), 4));
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::BTreeMap;
+
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+
+/// Cores usadas pela interface.
+///
+/// As combinações de frente e fundo ficam nos métodos, que no modo monocromático trocam as
+/// cores por negrito, sublinhado e vídeo reverso.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub monochrome: bool,
+    pub border: Color,
+    pub footer_border: Color,
+    pub header_fg: Color,
+    pub header_bg: Color,
+    pub selection_fg: Color,
+    pub selection_bg: Color,
+    pub marked_fg: Color,
+    pub marked_bg: Color,
+    pub added_fg: Color,
+    pub added_bg: Color,
+    pub jumped_fg: Color,
+    pub jumped_bg: Color,
+    pub removed: Color,
+    pub modal_fg: Color,
+    pub modal_bg: Color,
+    pub danger_fg: Color,
+    pub danger_bg: Color,
+    pub accent: Color,
+    pub muted: Color,
+    pub chart_connections: Color,
+    pub chart_memory: Color,
+    pub chart_instructions: Color,
+    pub chart_environment: Color,
+    pub chart_type: Color,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            monochrome: false,
+            border: Color::Blue,
+            footer_border: Color::Yellow,
+            header_fg: Color::White,
+            header_bg: Color::Black,
+            selection_fg: Color::Black,
+            selection_bg: Color::Gray,
+            marked_fg: Color::Black,
+            marked_bg: Color::LightRed,
+            added_fg: Color::Black,
+            added_bg: Color::Green,
+            jumped_fg: Color::Black,
+            jumped_bg: Color::Yellow,
+            removed: Color::Red,
+            modal_fg: Color::White,
+            modal_bg: Color::Black,
+            danger_fg: Color::White,
+            danger_bg: Color::Red,
+            accent: Color::Yellow,
+            muted: Color::DarkGray,
+            chart_connections: Color::Cyan,
+            chart_memory: Color::Magenta,
+            chart_instructions: Color::Green,
+            chart_environment: Color::Yellow,
+            chart_type: Color::LightBlue,
+        }
+    }
+
+    /// Para terminais de fundo claro: nada de branco sobre fundo padrão nem amarelo em texto.
+    pub fn light() -> Self {
+        Self {
+            border: Color::Blue,
+            footer_border: Color::Magenta,
+            header_fg: Color::Black,
+            header_bg: Color::Gray,
+            selection_fg: Color::White,
+            selection_bg: Color::Blue,
+            marked_fg: Color::White,
+            marked_bg: Color::Red,
+            added_fg: Color::Black,
+            added_bg: Color::LightGreen,
+            jumped_fg: Color::Black,
+            jumped_bg: Color::LightYellow,
+            removed: Color::Red,
+            modal_fg: Color::Black,
+            modal_bg: Color::White,
+            danger_fg: Color::White,
+            danger_bg: Color::Red,
+            accent: Color::Magenta,
+            muted: Color::Gray,
+            chart_connections: Color::Blue,
+            chart_memory: Color::Magenta,
+            chart_instructions: Color::Green,
+            chart_environment: Color::Red,
+            chart_type: Color::Blue,
+            ..Self::dark()
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            border: Color::White,
+            footer_border: Color::White,
+            header_fg: Color::Black,
+            header_bg: Color::White,
+            selection_fg: Color::Black,
+            selection_bg: Color::LightCyan,
+            marked_fg: Color::Black,
+            marked_bg: Color::LightMagenta,
+            added_fg: Color::Black,
+            added_bg: Color::LightGreen,
+            jumped_fg: Color::Black,
+            jumped_bg: Color::LightYellow,
+            removed: Color::LightRed,
+            modal_fg: Color::White,
+            modal_bg: Color::Black,
+            danger_fg: Color::Black,
+            danger_bg: Color::LightRed,
+            accent: Color::LightYellow,
+            muted: Color::White,
+            chart_connections: Color::LightCyan,
+            chart_memory: Color::LightMagenta,
+            chart_instructions: Color::LightGreen,
+            chart_environment: Color::LightYellow,
+            chart_type: Color::LightCyan,
+            ..Self::dark()
+        }
+    }
+
+    /// Sem cores, para `NO_COLOR` e terminais que não as exibem bem.
+    pub fn monochrome() -> Self {
+        Self {
+            monochrome: true,
+            ..Self::dark()
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "mono" => Some(Self::monochrome()),
+            _ => None,
+        }
+    }
+
+    /// Tema antes de ler o config.toml (ex.: na tela de carregamento): o escuro, ou o
+    /// monocromático com `NO_COLOR`.
+    pub fn initial() -> Self {
+        Self::load("dark", &BTreeMap::new()).unwrap_or_default()
+    }
+
+    /// Monta o tema configurado.
+    ///
+    /// `NO_COLOR` definida e não vazia força o modo monocromático. Um nome que não é embutido
+    /// procura `[themes.<nome>]` no config.toml, que parte do tema `base` e troca só as cores
+    /// informadas.
+    ///
+    /// # Errors
+    ///
+    /// Retorna o motivo se o tema não existir. O chamador decide se usa o padrão.
+    pub fn load(name: &str, themes: &BTreeMap<String, ThemeOverrides>) -> Result<Self, String> {
+        if std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty()) {
+            return Ok(Self::monochrome());
+        }
+        if let Some(theme) = Self::builtin(name) {
+            return Ok(theme);
+        }
+        let Some(custom) = themes.get(name) else {
+            return Err(format!("tema desconhecido: {} (use dark, light, high-contrast, mono ou [themes.{}])", name, name));
+        };
+        let base = custom.base.as_deref().unwrap_or("dark");
+        let Some(theme) = Self::builtin(base) else {
+            return Err(format!("tema base desconhecido em [themes.{}]: {}", name, base));
+        };
+        Ok(custom.apply(theme))
+    }
+
+    fn pair(&self, fg: Color, bg: Color, fallback: Modifier) -> Style {
+        if self.monochrome {
+            Style::default().add_modifier(fallback)
+        } else {
+            Style::default().fg(fg).bg(bg)
+        }
+    }
+
+    fn fg(&self, color: Color) -> Style {
+        if self.monochrome {
+            Style::default()
+        } else {
+            Style::default().fg(color)
+        }
+    }
+
+    pub fn border(&self) -> Style {
+        self.fg(self.border)
+    }
+
+    pub fn footer_border(&self) -> Style {
+        self.fg(self.footer_border)
+    }
+
+    pub fn header(&self) -> Style {
+        self.pair(self.header_fg, self.header_bg, Modifier::BOLD)
+    }
+
+    /// Linha sob o cursor.
+    pub fn selection(&self) -> Style {
+        self.pair(self.selection_fg, self.selection_bg, Modifier::REVERSED).add_modifier(Modifier::BOLD)
+    }
+
+    /// Linhas marcadas para ações em lote.
+    pub fn marked(&self) -> Style {
+        self.pair(self.marked_fg, self.marked_bg, Modifier::UNDERLINED).add_modifier(Modifier::BOLD)
+    }
+
+    pub fn added(&self) -> Style {
+        self.pair(self.added_fg, self.added_bg, Modifier::ITALIC)
+    }
+
+    pub fn jumped(&self) -> Style {
+        self.pair(self.jumped_fg, self.jumped_bg, Modifier::BOLD)
+    }
+
+    pub fn removed(&self) -> Style {
+        self.fg(self.removed).add_modifier(Modifier::CROSSED_OUT)
+    }
+
+    pub fn modal(&self) -> Style {
+        self.pair(self.modal_fg, self.modal_bg, Modifier::empty())
+    }
+
+    /// Modais de confirmação de desconexão e de erro.
+    pub fn danger(&self) -> Style {
+        self.pair(self.danger_fg, self.danger_bg, Modifier::BOLD)
+    }
+
+    /// Itens destacados dentro dos modais, como o item escolhido de uma lista.
+    pub fn highlight(&self) -> Style {
+        self.pair(self.selection_fg, self.selection_bg, Modifier::REVERSED).add_modifier(Modifier::BOLD)
+    }
+
+    pub fn accent(&self) -> Style {
+        self.fg(self.accent)
+    }
+
+    pub fn muted(&self) -> Style {
+        if self.monochrome {
+            Style::default().add_modifier(Modifier::DIM)
+        } else {
+            Style::default().fg(self.muted)
+        }
+    }
+
+    pub fn added_text(&self) -> Style {
+        self.fg(self.added_bg)
+    }
+
+    pub fn error_text(&self) -> Style {
+        self.fg(self.removed)
+    }
+
+    /// Cor de um gráfico; no modo monocromático usa a cor padrão do terminal.
+    pub fn chart(&self, color: Color) -> Color {
+        if self.monochrome { Color::Reset } else { color }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+
+/// Tema do usuário em `[themes.<nome>]`: parte de um tema embutido e troca as cores informadas.
+///
+/// As cores aceitam nomes (`blue`, `lightred`), índices de 256 cores (`208`) e `#rrggbb`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ThemeOverrides {
+    pub base: Option<String>,
+    pub border: Option<Color>,
+    pub footer_border: Option<Color>,
+    pub header_fg: Option<Color>,
+    pub header_bg: Option<Color>,
+    pub selection_fg: Option<Color>,
+    pub selection_bg: Option<Color>,
+    pub marked_fg: Option<Color>,
+    pub marked_bg: Option<Color>,
+    pub added_fg: Option<Color>,
+    pub added_bg: Option<Color>,
+    pub jumped_fg: Option<Color>,
+    pub jumped_bg: Option<Color>,
+    pub removed: Option<Color>,
+    pub modal_fg: Option<Color>,
+    pub modal_bg: Option<Color>,
+    pub danger_fg: Option<Color>,
+    pub danger_bg: Option<Color>,
+    pub accent: Option<Color>,
+    pub muted: Option<Color>,
+    pub chart_connections: Option<Color>,
+    pub chart_memory: Option<Color>,
+    pub chart_instructions: Option<Color>,
+    pub chart_environment: Option<Color>,
+    pub chart_type: Option<Color>,
+}
+
+impl ThemeOverrides {
+    fn apply(&self, mut theme: Theme) -> Theme {
+        macro_rules! apply {
+            ($($field:ident),*) => {
+                $(if let Some(color) = self.$field {
+                    theme.$field = color;
+                })*
+            };
+        }
+        apply!(
+            border, footer_border, header_fg, header_bg, selection_fg, selection_bg, marked_fg, marked_bg,
+            added_fg, added_bg, jumped_fg, jumped_bg, removed, modal_fg, modal_bg, danger_fg, danger_bg,
+            accent, muted, chart_connections, chart_memory, chart_instructions, chart_environment, chart_type
+        );
+        theme
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Paragraph, Sparkline},
+    Frame,
+};
+
+use crate::{api_service::Entry, theme::Theme};
+
+/// Quantidade máxima de amostras mantidas na sessão.
+const CAPACITY: usize = 240;
+
+
+/// Totais de uma atualização da listagem completa.
+pub struct Sample {
+    pub connections: u64,
+    pub memory: u64,
+    pub instructions_ps: u64,
+    pub by_environment: BTreeMap<String, u64>,
+    pub by_type: BTreeMap<String, u64>,
+    pub users: usize,
+    pub machines: usize,
+    /// Usuário, programa e tempo da conexão inativa há mais tempo.
+    pub longest_idle: Option<(String, String, String)>,
+}
+
+impl Sample {
+    pub fn from_entries(entries: &[Entry]) -> Self {
+        let mut by_environment: BTreeMap<String, u64> = BTreeMap::new();
+        let mut by_type: BTreeMap<String, u64> = BTreeMap::new();
+        for entry in entries {
+            *by_environment.entry(entry.environment.trim().to_string()).or_default() += 1;
+            *by_type.entry(entry.thread_type.trim().to_string()).or_default() += 1;
+        }
+
+        let users: HashSet<&str> = entries.iter().map(|e| e.user_name.trim()).collect();
+        let machines: HashSet<&str> = entries.iter().map(|e| e.machine_name.trim()).collect();
+        let longest_idle = entries
+            .iter()
+            .filter_map(|e| e.inactive_secs().map(|secs| (secs, e)))
+            .max_by_key(|(secs, _)| *secs)
+            .map(|(_, e)| (e.user_name.trim().to_string(), e.function.trim().to_string(), e.inactive_time.trim().to_string()));
+
+        Self {
+            connections: entries.len() as u64,
+            memory: entries.iter().map(|e| e.memory.max(0) as u64).sum(),
+            instructions_ps: entries.iter().map(|e| e.instructions_ps.max(0) as u64).sum(),
+            by_environment,
+            by_type,
+            users: users.len(),
+            machines: machines.len(),
+            longest_idle,
+        }
+    }
+}
+
+
+/// Histórico em memória das amostras coletadas desde que o monitor foi aberto.
+#[derive(Default)]
+pub struct Trends {
+    samples: VecDeque<Sample>,
+}
+
+impl Trends {
+    pub fn push(&mut self, entries: &[Entry]) {
+        if self.samples.len() == CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample::from_entries(entries));
+    }
+
+    pub fn latest(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    fn series(&self, width: u16, value: impl Fn(&Sample) -> u64) -> Vec<u64> {
+        let skip = self.samples.len().saturating_sub(width as usize);
+        self.samples.iter().skip(skip).map(value).collect()
+    }
+}
+
+
+/// Formata um valor de memória em bytes usando a maior unidade que couber.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+
+/// Renderiza o painel de tendências ao lado da tabela.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `area` - área reservada para o painel.
+/// * `trends` - amostras coletadas na sessão.
+/// * `listing_error` - erro da última atualização da listagem completa, se houver.
+pub fn draw_dashboard(f: &mut Frame, theme: &Theme, area: Rect, trends: &Trends, listing_error: Option<&str>) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Length(4),
+            Constraint::Length(4),
+            Constraint::Min(5),
+            Constraint::Min(5),
+        ])
+        .split(area);
+
+    let Some(latest) = trends.latest() else {
+        let title = listing_error.unwrap_or("aguardando a primeira atualização");
+        f.render_widget(
+            Block::default().title(title.to_string()).borders(Borders::ALL).border_style(theme.border()),
+            area,
+        );
+        return;
+    };
+
+    let sparklines = [
+        (format!("conexões: {}", latest.connections), theme.chart_connections, trends.series(chunks[0].width.saturating_sub(2), |s| s.connections)),
+        (format!("memória: {}", format_bytes(latest.memory)), theme.chart_memory, trends.series(chunks[1].width.saturating_sub(2), |s| s.memory)),
+        (format!("instruções/s: {}", latest.instructions_ps), theme.chart_instructions, trends.series(chunks[2].width.saturating_sub(2), |s| s.instructions_ps)),
+    ];
+
+    for (i, (title, color, data)) in sparklines.into_iter().enumerate() {
+        let mut block = Block::default().title(title).borders(Borders::ALL).border_style(theme.border());
+        if i == 0 && let Some(e) = listing_error {
+            block = block.title_bottom(Span::styled(format!("erro: {}", e), theme.error_text()));
+        }
+        let sparkline = Sparkline::default()
+            .block(block)
+            .data(&data)
+            .style(Style::default().fg(theme.chart(color)));
+        f.render_widget(sparkline, chunks[i]);
+    }
+
+    draw_breakdown(f, theme, chunks[3], "por ambiente", &latest.by_environment, theme.chart_environment);
+    draw_breakdown(f, theme, chunks[4], "por tipo de conexão", &latest.by_type, theme.chart_type);
+}
+
+
+fn draw_breakdown(f: &mut Frame, theme: &Theme, area: Rect, title: &str, counts: &BTreeMap<String, u64>, color: Color) {
+    let color = theme.chart(color);
+    let bars: Vec<Bar> = counts
+        .iter()
+        .map(|(label, count)| {
+            Bar::default()
+                .label(label.clone().into())
+                .value(*count)
+                .style(Style::default().fg(color))
+                .value_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED))
+        })
+        .collect();
+
+    let chart = BarChart::default()
+        .block(Block::default().title(title.to_string()).borders(Borders::ALL).border_style(theme.border()))
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .data(BarGroup::default().bars(&bars));
+
+    f.render_widget(chart, area);
+}
+
+
+/// Renderiza o cabeçalho com os totais da última atualização da listagem completa.
+///
+/// # Argumentos
+///
+/// * `f` - frame que será renderizado.
+/// * `theme` - tema da sessão.
+/// * `area` - área reservada para o cabeçalho.
+/// * `trends` - amostras coletadas na sessão; apenas a mais recente é exibida.
+pub fn draw_summary(f: &mut Frame, theme: &Theme, area: Rect, trends: &Trends) {
+    let block = Block::default()
+        .title("Resumo")
+        .borders(Borders::ALL)
+        .border_style(theme.border());
+
+    let Some(latest) = trends.latest() else {
+        f.render_widget(Paragraph::new("aguardando a primeira atualização").block(block), area);
+        return;
+    };
+
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let idle = match &latest.longest_idle {
+        Some((user, function, time)) => format!("{} {} ({})", user, function, time),
+        None => "-".to_string(),
+    };
+    let join = |counts: &BTreeMap<String, u64>| {
+        counts.iter().map(|(k, v)| format!("{} {}", k, v)).collect::<Vec<_>>().join(", ")
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::raw("Conexões: "), Span::styled(latest.connections.to_string(), bold),
+            Span::raw("  Usuários: "), Span::styled(latest.users.to_string(), bold),
+            Span::raw("  Computadores: "), Span::styled(latest.machines.to_string(), bold),
+            Span::raw("  Memória: "), Span::styled(format_bytes(latest.memory), bold),
+            Span::raw("  Maior inatividade: "), Span::styled(idle, bold),
+        ]),
+        Line::from(vec![
+            Span::raw("Ambientes: "), Span::styled(join(&latest.by_environment), bold),
+            Span::raw("  Tipos: "), Span::styled(join(&latest.by_type), bold),
+        ]),
+    ];
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}